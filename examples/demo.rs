@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use plain_text_db_rust::{append_data, create_db, load_db, read_data, render_db};
+
+fn main() {
+    create_db("test");
+    //let db: HashMap<String,HashMap<String,String>> = load_db("test");
+    //write_db(db);
+    //let db: HashMap<String,HashMap<String,String>> = load_db("test");
+    //let data = read_data(db, "temp_group".to_string(), "temp".to_string());
+    //println!("{}",data);
+    let db: HashMap<String,HashMap<String,String>> = load_db("test");
+    if let Err(why) = append_data(db, "data".to_string(), "temp_data".to_string(), "false".to_string()) {
+        panic!("couldn't append data {}", why)
+    }
+    let db: HashMap<String,HashMap<String,String>> = load_db("test");
+    print!("{}", render_db(&db));
+    match read_data(db, "data".to_string(), "temp_data".to_string()) {
+        Ok(data) => println!("{}",data),
+        Err(why) => println!("{}",why),
+    }
+}
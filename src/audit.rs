@@ -0,0 +1,216 @@
+use serde_json::{json, Value};
+
+use crate::error::{Error, ErrorKind};
+use crate::store::KvStore;
+use crate::timestamps::now;
+use crate::Db;
+
+// Records are kept in the name group, so they are saved with the database but
+// never listed as a group. The number after the prefix orders them.
+const AUDIT_PREFIX: &str = "audit:";
+
+/// The KvStore write an AuditRecord is for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditOp {
+    CreateGroup,
+    Put,
+    RemoveEntry,
+    RemoveGroup,
+}
+
+impl AuditOp {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuditOp::CreateGroup => "create_group",
+            AuditOp::Put => "put",
+            AuditOp::RemoveEntry => "remove_data_entry",
+            AuditOp::RemoveGroup => "remove_group",
+        }
+    }
+
+    fn parse(op: &str) -> Option<AuditOp> {
+        Some(match op {
+            "create_group" => AuditOp::CreateGroup,
+            "put" => AuditOp::Put,
+            "remove_data_entry" => AuditOp::RemoveEntry,
+            "remove_group" => AuditOp::RemoveGroup,
+            _ => return None,
+        })
+    }
+}
+
+/// One write made through an AuditStore.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub actor: String,
+    pub op: AuditOp,
+    pub group_name: String,
+    /// None for the group operations.
+    pub data_name: Option<String>,
+    /// Bytes of data written by a put or taken away by a remove_data_entry,
+    /// 0 for the group operations.
+    pub value_len: usize,
+}
+
+/// Wraps a database and appends an AuditRecord to it for every write made
+/// through the wrapper that changed something. The record is written in the
+/// same call as the write, before it returns. Wrap a PersistentDb through
+/// its DerefMut so the records count towards the next save.
+pub struct AuditStore<'a> {
+    db: &'a mut Db,
+    actor: String,
+    next_seq: u64,
+}
+
+impl<'a> AuditStore<'a> {
+    pub fn new(db: &'a mut Db, actor: &str) -> AuditStore<'a> {
+        let next_seq = record_keys(db).last().map_or(0, |(seq, _)| seq + 1);
+        AuditStore {
+            db,
+            actor: actor.to_string(),
+            next_seq,
+        }
+    }
+
+    /// Who the following writes are recorded as made by.
+    pub fn set_actor(&mut self, actor: &str) {
+        self.actor = actor.to_string();
+    }
+
+    /// The records made at or after since, every record when None, oldest
+    /// first.
+    pub fn audit_log(&self, since: Option<u64>) -> Result<Vec<AuditRecord>, Error> {
+        let mut records = Vec::new();
+        for (seq, key) in record_keys(self.db) {
+            let record = parse_record(&self.db["name"][&key], seq)?;
+            if since.is_none_or(|since| record.timestamp >= since) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Removes the records made before before, returning how many went.
+    pub fn truncate_audit(&mut self, before: u64) -> Result<usize, Error> {
+        let mut removed = 0;
+        for (seq, key) in record_keys(self.db) {
+            let name_group = self.db.get_mut("name").expect("records are in it");
+            if parse_record(&name_group[&key], seq)?.timestamp < before {
+                name_group.remove(&key);
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn record(&mut self, op: AuditOp, group_name: &str, data_name: Option<&str>, value_len: usize) {
+        let record = json!({
+            "timestamp": now(),
+            "actor": self.actor,
+            "op": op.as_str(),
+            "group_name": group_name,
+            "data_name": data_name,
+            "value_len": value_len,
+        });
+        let key = format!("{}{}", AUDIT_PREFIX, self.next_seq);
+        self.next_seq += 1;
+        self.db
+            .entry("name".to_string())
+            .or_default()
+            .insert(key, record.to_string());
+    }
+}
+
+// Sorted by sequence number.
+fn record_keys(db: &Db) -> Vec<(u64, String)> {
+    let mut keys: Vec<(u64, String)> = db
+        .get("name")
+        .into_iter()
+        .flat_map(|name_group| name_group.keys())
+        .filter_map(|key| Some((key.strip_prefix(AUDIT_PREFIX)?.parse().ok()?, key.clone())))
+        .collect();
+    keys.sort();
+    keys
+}
+
+fn parse_record(record: &str, seq: u64) -> Result<AuditRecord, Error> {
+    let corrupt = |why: &str| {
+        Error::new(
+            ErrorKind::Corrupt,
+            format!("audit record {} is invalid: {}", seq, why),
+        )
+    };
+    let record: Value = serde_json::from_str(record).map_err(|why| corrupt(&why.to_string()))?;
+    let text = |field: &str| {
+        record[field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| corrupt(&format!("{} is missing", field)))
+    };
+    let number = |field: &str| {
+        record[field]
+            .as_u64()
+            .ok_or_else(|| corrupt(&format!("{} is missing", field)))
+    };
+    Ok(AuditRecord {
+        timestamp: number("timestamp")?,
+        actor: text("actor")?,
+        op: AuditOp::parse(&text("op")?).ok_or_else(|| corrupt("unknown op"))?,
+        group_name: text("group_name")?,
+        data_name: record["data_name"].as_str().map(str::to_string),
+        value_len: number("value_len")? as usize,
+    })
+}
+
+impl KvStore for AuditStore<'_> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        let created = self.db.create_group(group_name);
+        if created {
+            self.record(AuditOp::CreateGroup, group_name, None, 0);
+        }
+        created
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let value_len = data.len();
+        let replaced = self.db.put(group_name, data_name, data)?;
+        self.record(AuditOp::Put, group_name, Some(data_name), value_len);
+        Ok(replaced)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.db.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let removed = self.db.remove_data_entry(group_name, data_name)?;
+        self.record(
+            AuditOp::RemoveEntry,
+            group_name,
+            Some(data_name),
+            removed.len(),
+        );
+        Some(removed)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.db.remove_group(group_name)?;
+        self.record(AuditOp::RemoveGroup, group_name, None, 0);
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.db.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.db.list_data_names(group_name)
+    }
+}
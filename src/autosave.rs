@@ -0,0 +1,75 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::manager::{lock, SharedDb};
+
+/// Saves a shared PersistentDb every interval on a thread of its own, only
+/// when its generation moved since the last save.
+///
+/// Dropping the handle without stop ends the thread without a final save.
+/// Writes since the last autosave are then only kept if the PersistentDb is
+/// dropped normally, which saves it, and are lost if the process dies first.
+pub struct AutosaveHandle {
+    db: SharedDb,
+    stopping: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Starts the thread, the first save is due one interval from now.
+pub fn start_autosave(db: SharedDb, interval: Duration) -> AutosaveHandle {
+    let stopping = Arc::new((Mutex::new(false), Condvar::new()));
+    let thread = {
+        let db = db.clone();
+        let stopping = stopping.clone();
+        std::thread::spawn(move || loop {
+            let (stop, wake) = &*stopping;
+            let (stop, _) = wake
+                .wait_timeout_while(lock(stop), interval, |stop| !*stop)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if *stop {
+                return;
+            }
+            drop(stop);
+            let mut db = lock(&db);
+            if let Err(why) = db.save_if_changed() {
+                eprintln!("couldn't autosave {}: {}", db.path().display(), why);
+            }
+        })
+    };
+    AutosaveHandle {
+        db,
+        stopping,
+        thread: Some(thread),
+    }
+}
+
+impl AutosaveHandle {
+    /// Saves now if anything changed, without waiting for the interval.
+    /// Returns whether it saved.
+    pub fn flush_now(&self) -> Result<bool, Error> {
+        lock(&self.db).save_if_changed()
+    }
+
+    /// Ends the thread and makes a final save, returning its error.
+    pub fn stop(mut self) -> Result<(), Error> {
+        self.end_thread();
+        self.flush_now().map(|_| ())
+    }
+
+    fn end_thread(&mut self) {
+        let (stop, wake) = &*self.stopping;
+        *lock(stop) = true;
+        wake.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for AutosaveHandle {
+    fn drop(&mut self) {
+        self.end_thread();
+    }
+}
@@ -0,0 +1,45 @@
+// Standard alphabet with padding, for bytes that have to be stored or sent
+// as text.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+pub(crate) fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+    for chunk in encoded.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, &c) in chunk[..4 - padding].iter().enumerate() {
+            let value = ALPHABET.iter().position(|&a| a == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..3 - padding {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,315 @@
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+use plain_text_db_rust::{
+    atomic_save, export_group_csv, get_data, import_ndjson, insert_data, list_data_names,
+    list_groups, load_from_file, read_as, read_value, remove_data_entry, remove_group,
+    to_json_string, to_ndjson, to_plain_text, Db, Error, ErrorKind,
+};
+
+const USAGE: &str = "usage: ptdb --file <path> <command>";
+
+// Also the repl's help, less repl itself.
+const COMMANDS: &str = "commands:
+    repl
+    list-groups
+    list-data-names <group>
+    get <group> <data_name> [--as string|int|float|bool|json|hex]
+    set <group> <data_name> <data> [--type string|int|float|bool]
+    rm <group> [<data_name>]
+    export --format json|ndjson|plain|csv [<group>]
+    import <ndjson path, - for stdin> [--overwrite]";
+
+// Exit codes, so scripts can tell the failures apart.
+const EXIT_NOT_FOUND: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_CORRUPT: u8 = 3;
+const EXIT_OTHER: u8 = 4;
+
+enum Failure {
+    Usage(String),
+    Db(Error),
+}
+
+impl From<Error> for Failure {
+    fn from(why: Error) -> Failure {
+        Failure::Db(why)
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(why)) => {
+            eprintln!("ptdb: {}\n\n{}\n\n{}", why, USAGE, COMMANDS);
+            ExitCode::from(EXIT_USAGE)
+        }
+        Err(Failure::Db(why)) => {
+            eprintln!("ptdb: {}", why);
+            ExitCode::from(match why.kind() {
+                ErrorKind::NotFound | ErrorKind::GroupNotFound | ErrorKind::DataNotFound => {
+                    EXIT_NOT_FOUND
+                }
+                ErrorKind::Corrupt
+                | ErrorKind::ChecksumMismatch
+                | ErrorKind::UnsupportedVersion => EXIT_CORRUPT,
+                ErrorKind::Parse | ErrorKind::TypeMismatch => EXIT_USAGE,
+                _ => EXIT_OTHER,
+            })
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Failure> {
+    let args = Args::parse(args)?;
+    if args.help {
+        println!("{}\n\n{}", USAGE, COMMANDS);
+        return Ok(());
+    }
+    let Some(path) = args.path else {
+        return Err(Failure::Usage("--file is required".to_string()));
+    };
+    let mut db = match load_from_file(path) {
+        // Commands that write start a new file, the rest have nothing to read.
+        Err(why) if why.kind() == ErrorKind::NotFound && args.mutates() => Db::new(),
+        loaded => loaded?,
+    };
+    if args.words == ["repl"] {
+        return repl(&mut db, path);
+    }
+    if run_command(&mut db, &args)? {
+        atomic_save(&db, path)?;
+    }
+    Ok(())
+}
+
+// The command line split into --file, the other options and the command.
+struct Args<'a> {
+    path: Option<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+    words: Vec<&'a str>,
+    help: bool,
+}
+
+impl<'a> Args<'a> {
+    fn parse(args: &'a [impl AsRef<str>]) -> Result<Args<'a>, Failure> {
+        let mut parsed = Args {
+            path: None,
+            options: Vec::new(),
+            words: Vec::new(),
+            help: false,
+        };
+        let mut args = args.iter().map(AsRef::as_ref);
+        while let Some(arg) = args.next() {
+            match arg {
+                "--file" => parsed.path = Some(option_value(&mut args, arg)?),
+                "--as" | "--type" | "--format" => {
+                    parsed.options.push((arg, option_value(&mut args, arg)?))
+                }
+                "--overwrite" => parsed.options.push((arg, "")),
+                "-h" | "--help" => parsed.help = true,
+                _ if arg.starts_with("--") => {
+                    return Err(Failure::Usage(format!("unknown option {}", arg)))
+                }
+                _ => parsed.words.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn mutates(&self) -> bool {
+        matches!(self.words.first(), Some(&("set" | "import" | "repl")))
+    }
+
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+// Returns whether db changed and needs saving.
+fn run_command(db: &mut Db, args: &Args) -> Result<bool, Failure> {
+    match args.words.as_slice() {
+        ["list-groups"] => print_lines(&list_groups(db)),
+        ["list-data-names", group_name] => print_lines(&list_data_names(db, group_name)?),
+        ["get", group_name, data_name] => println!(
+            "{}",
+            read_typed(db, group_name, data_name, args.option("--as"))?
+        ),
+        ["set", group_name, data_name, data] => {
+            check_type(group_name, data_name, data, args.option("--type"))?;
+            insert_data(db, group_name, data_name, data.to_string(), true)?;
+            return Ok(true);
+        }
+        ["rm", group_name] => {
+            // For the GroupNotFound error.
+            list_data_names(db, group_name)?;
+            remove_group(db, group_name);
+            return Ok(true);
+        }
+        ["rm", group_name, data_name] => {
+            get_data(db, group_name, data_name)?;
+            remove_data_entry(db, group_name, data_name);
+            return Ok(true);
+        }
+        ["export"] | ["export", _] => {
+            let exported = match (
+                args.option("--format").unwrap_or("plain"),
+                args.words.get(1),
+            ) {
+                ("json", None) => to_json_string(db),
+                ("ndjson", None) => to_ndjson(db),
+                ("plain", None) => to_plain_text(db),
+                ("csv", Some(group_name)) => export_group_csv(db, group_name)?,
+                ("csv", None) => {
+                    return Err(Failure::Usage("csv exports one group, name it".to_string()))
+                }
+                (format, _) => {
+                    return Err(Failure::Usage(format!("can't export {} like that", format)))
+                }
+            };
+            print!("{}", exported);
+        }
+        ["import", source] => {
+            let ndjson = read_source(source)?;
+            let written = import_ndjson(db, &ndjson, args.option("--overwrite").is_some())?;
+            println!("imported {} entries", written);
+            return Ok(written > 0);
+        }
+        [] => return Err(Failure::Usage("no command given".to_string())),
+        _ => {
+            return Err(Failure::Usage(format!(
+                "can't understand '{}'",
+                args.words.join(" ")
+            )))
+        }
+    }
+    Ok(false)
+}
+
+// The same commands one line at a time. Changes are only written by save,
+// quit refuses to throw them away, quit! does anyway.
+fn repl(db: &mut Db, path: &str) -> Result<(), Failure> {
+    let mut history = history_file();
+    let mut dirty = false;
+    let mut line = String::new();
+    loop {
+        print!("ptdb{}> ", if dirty { "*" } else { "" });
+        let _ = std::io::stdout().flush();
+        line.clear();
+        let read = std::io::stdin()
+            .read_line(&mut line)
+            .map_err(|why| Failure::Usage(format!("couldn't read stdin: {}", why)))?;
+        if read == 0 {
+            println!();
+            if dirty {
+                eprintln!("ptdb: unsaved changes discarded");
+            }
+            return Ok(());
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        if let Some(history) = &mut history {
+            let _ = writeln!(history, "{}", words.join(" "));
+        }
+        match words.as_slice() {
+            ["help"] => println!(
+                "{}\n    save\n    quit\n    quit!",
+                COMMANDS.replace("\n    repl", "")
+            ),
+            ["save"] => match atomic_save(db, path) {
+                Ok(()) => dirty = false,
+                Err(why) => eprintln!("ptdb: {}", why),
+            },
+            ["quit"] if dirty => eprintln!("ptdb: unsaved changes, save first or quit! to discard"),
+            ["quit"] | ["quit!"] => return Ok(()),
+            _ => match Args::parse(&words).and_then(|args| run_command(db, &args)) {
+                Ok(changed) => dirty |= changed,
+                Err(Failure::Usage(why)) => eprintln!("ptdb: {}", why),
+                Err(Failure::Db(why)) => eprintln!("ptdb: {}", why),
+            },
+        }
+    }
+}
+
+// ~/.ptdb_history, opened for appending. History is a convenience, the repl
+// works without it.
+fn history_file() -> Option<std::fs::File> {
+    let home = std::env::var_os("HOME")?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(std::path::Path::new(&home).join(".ptdb_history"))
+        .ok()
+}
+
+fn option_value<'a>(
+    args: &mut impl Iterator<Item = &'a str>,
+    name: &str,
+) -> Result<&'a str, Failure> {
+    args.next()
+        .ok_or_else(|| Failure::Usage(format!("{} needs a value", name)))
+}
+
+fn print_lines(lines: &[String]) {
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+fn read_typed(
+    db: &Db,
+    group_name: &str,
+    data_name: &str,
+    as_type: Option<&str>,
+) -> Result<String, Failure> {
+    Ok(match as_type.unwrap_or("string") {
+        "string" => get_data(db, group_name, data_name)?.into_owned(),
+        "int" => read_as::<i64>(db, group_name, data_name)?.to_string(),
+        "float" => read_as::<f64>(db, group_name, data_name)?.to_string(),
+        "bool" => read_as::<bool>(db, group_name, data_name)?.to_string(),
+        "json" => read_value(db, group_name, data_name, false)?.to_string(),
+        // Two lowercase digits per byte of the UTF-8 data.
+        "hex" => get_data(db, group_name, data_name)?
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+        other => return Err(Failure::Usage(format!("unknown type {}", other))),
+    })
+}
+
+// Refuses data that isn't the type it is meant to be, before it is stored.
+fn check_type(
+    group_name: &str,
+    data_name: &str,
+    data: &str,
+    as_type: Option<&str>,
+) -> Result<(), Failure> {
+    if as_type == Some("hex") {
+        return Err(Failure::Usage(
+            "hex is only for showing data, set takes it as text".to_string(),
+        ));
+    }
+    let mut check = Db::new();
+    check
+        .entry(group_name.to_string())
+        .or_default()
+        .insert(data_name.to_string(), data.to_string());
+    read_typed(&check, group_name, data_name, as_type).map(|_| ())
+}
+
+fn read_source(source: &str) -> Result<String, Failure> {
+    let mut contents = String::new();
+    let read = if source == "-" {
+        std::io::stdin().read_to_string(&mut contents).map(|_| ())
+    } else {
+        std::fs::read_to_string(source).map(|read| contents = read)
+    };
+    read.map_err(|why| Failure::Usage(format!("couldn't read {}: {}", source, why)))?;
+    Ok(contents)
+}
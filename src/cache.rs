@@ -0,0 +1,50 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::db::{get_data, read_as};
+use crate::error::Error;
+use crate::Db;
+
+/// Remembers one entry parsed as T, for values read far more often than they
+/// are written. The data the value was parsed from is kept with it, get only
+/// parses again when the stored data differs, so a write through any
+/// function is never missed.
+pub struct CachedField<T> {
+    group_name: String,
+    data_name: String,
+    cached: Option<(String, Arc<T>)>,
+}
+
+/// Nothing is read until the first get.
+pub fn cached<T>(group_name: &str, data_name: &str) -> CachedField<T> {
+    CachedField {
+        group_name: group_name.to_string(),
+        data_name: data_name.to_string(),
+        cached: None,
+    }
+}
+
+impl<T: FromStr> CachedField<T>
+where
+    T::Err: fmt::Display,
+{
+    /// The entry parsed as T, the cached value when its data hasn't changed.
+    /// Errors are the same as read_as and aren't cached.
+    pub fn get(&mut self, db: &Db) -> Result<Arc<T>, Error> {
+        let data = get_data(db, &self.group_name, &self.data_name)?;
+        if let Some((cached_data, value)) = &self.cached {
+            if *cached_data == data {
+                return Ok(Arc::clone(value));
+            }
+        }
+        let value = Arc::new(read_as::<T>(db, &self.group_name, &self.data_name)?);
+        self.cached = Some((data.into_owned(), Arc::clone(&value)));
+        Ok(value)
+    }
+
+    /// Drops the cached value so the next get parses again.
+    pub fn invalidate(&mut self) {
+        self.cached = None;
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::{Error, ErrorKind};
+use crate::format::{from_bytes_auto, to_plain_text};
+use crate::json::{from_json_str, to_json_string};
+use crate::Db;
+
+/// Turns a whole database into the bytes of a file and back.
+pub trait Codec {
+    fn encode(&self, db: &Db) -> Result<Vec<u8>, Error>;
+    fn decode(&self, bytes: &[u8]) -> Result<Db, Error>;
+}
+
+/// The .pdbr format, decoding also accepts compressed files.
+pub struct PlainTextCodec;
+
+impl Codec for PlainTextCodec {
+    fn encode(&self, db: &Db) -> Result<Vec<u8>, Error> {
+        Ok(to_plain_text(db).into_bytes())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Db, Error> {
+        from_bytes_auto(bytes)
+    }
+}
+
+/// to_json_string/from_json_str.
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode(&self, db: &Db) -> Result<Vec<u8>, Error> {
+        Ok(to_json_string(db).into_bytes())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Db, Error> {
+        let contents = std::str::from_utf8(bytes).map_err(|_| {
+            Error::new(ErrorKind::Corrupt, "not valid UTF-8".to_string())
+        })?;
+        from_json_str(contents)
+    }
+}
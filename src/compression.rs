@@ -0,0 +1,127 @@
+#[cfg(feature = "compression")]
+use std::io::{Read, Write};
+
+#[cfg(feature = "compression")]
+use crate::base64;
+use crate::error::{Error, ErrorKind};
+#[cfg(feature = "compression")]
+use crate::format::to_plain_text;
+#[cfg(feature = "compression")]
+use crate::Db;
+
+// Can never start valid UTF-8, so it doesn't collide with a plain .pdbr file.
+pub(crate) const COMPRESSED_MAGIC: &[u8; 4] = b"\xffPDZ";
+
+/// How hard to_compressed_bytes tries, trading speed for size.
+#[cfg(feature = "compression")]
+pub enum CompressionLevel {
+    Fast,
+    Default,
+    Best,
+}
+
+/// COMPRESSED_MAGIC, the plain text length as a little endian u64, then the
+/// plain text as a zlib stream.
+#[cfg(feature = "compression")]
+pub fn to_compressed_bytes(db: &Db, level: CompressionLevel) -> Vec<u8> {
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    let level = match level {
+        CompressionLevel::Fast => Compression::fast(),
+        CompressionLevel::Default => Compression::default(),
+        CompressionLevel::Best => Compression::best(),
+    };
+    let plain_text = to_plain_text(db);
+    let mut bytes = COMPRESSED_MAGIC.to_vec();
+    bytes.extend_from_slice(&(plain_text.len() as u64).to_le_bytes());
+    let mut encoder = ZlibEncoder::new(bytes, level);
+    // Writing into a Vec can't fail.
+    encoder
+        .write_all(plain_text.as_bytes())
+        .expect("compressing into memory failed");
+    encoder.finish().expect("compressing into memory failed")
+}
+
+// Text usually compresses to well under this ratio, more is grown into.
+#[cfg(feature = "compression")]
+const PREALLOCATE_RATIO: usize = 8;
+
+#[cfg(feature = "compression")]
+pub(crate) fn decompress(compressed: &[u8]) -> Result<String, Error> {
+    use flate2::read::ZlibDecoder;
+
+    let corrupt = |message: String| Error::new(ErrorKind::Corrupt, message);
+    let Some((len, stream)) = compressed.split_first_chunk::<8>() else {
+        return Err(corrupt("compressed header is truncated".to_string()));
+    };
+    // The length comes from the file, so it only bounds what is read and how
+    // much is allocated up front, it isn't trusted to be right.
+    let len = u64::from_le_bytes(*len);
+    let reserve = len.min(stream.len().saturating_mul(PREALLOCATE_RATIO) as u64);
+    let mut plain_text = String::with_capacity(reserve as usize);
+    ZlibDecoder::new(stream)
+        .take(len.saturating_add(1))
+        .read_to_string(&mut plain_text)
+        .map_err(|why| corrupt(format!("could not decompress: {}", why)))?;
+    if plain_text.len() as u64 != len {
+        return Err(corrupt(format!(
+            "decompressed {} bytes but the header says {}",
+            plain_text.len(),
+            len
+        )));
+    }
+    Ok(plain_text)
+}
+
+// The same length and zlib stream as to_compressed_bytes without the magic,
+// then base64 so it is still text and can be stored as data.
+#[cfg(feature = "compression")]
+pub(crate) fn compress_text(text: &str) -> Result<String, Error> {
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    let bytes = (text.len() as u64).to_le_bytes().to_vec();
+    let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+    // Writing into a Vec can't fail.
+    encoder
+        .write_all(text.as_bytes())
+        .expect("compressing into memory failed");
+    let compressed = encoder.finish().expect("compressing into memory failed");
+    Ok(base64::encode(&compressed))
+}
+
+#[cfg(feature = "compression")]
+pub(crate) fn decompress_text(packed: &str) -> Result<String, Error> {
+    let Some(compressed) = base64::decode(packed) else {
+        return Err(Error::new(
+            ErrorKind::Corrupt,
+            "compressed data is not valid base64".to_string(),
+        ));
+    };
+    decompress(&compressed)
+}
+
+#[cfg(not(feature = "compression"))]
+pub(crate) fn decompress(_compressed: &[u8]) -> Result<String, Error> {
+    Err(Error::new(
+        ErrorKind::Corrupt,
+        "database is compressed but the compression feature is disabled".to_string(),
+    ))
+}
+
+#[cfg(not(feature = "compression"))]
+pub(crate) fn compress_text(_text: &str) -> Result<String, Error> {
+    Err(Error::new(
+        ErrorKind::Corrupt,
+        "compressing data needs the compression feature".to_string(),
+    ))
+}
+
+#[cfg(not(feature = "compression"))]
+pub(crate) fn decompress_text(_packed: &str) -> Result<String, Error> {
+    Err(Error::new(
+        ErrorKind::Corrupt,
+        "data is compressed but the compression feature is disabled".to_string(),
+    ))
+}
@@ -0,0 +1,161 @@
+use std::borrow::Cow;
+
+use crate::compression::{compress_text, decompress_text};
+use crate::db::{get_data, insert_data};
+use crate::error::{Error, ErrorKind};
+use crate::format::from_plain_text;
+use crate::history::is_keep_key;
+use crate::timestamps::{entry_meta_value, is_entry_meta_key, set_entry_meta_value};
+use crate::Db;
+
+/// Limits on group names, data_names and data. The default allows anything,
+/// so only callers that opt in are checked.
+#[derive(Debug, Clone)]
+pub struct DbConfig {
+    /// Longest group name or data_name, in bytes.
+    pub max_key_len: usize,
+    pub allow_empty_keys: bool,
+    /// Largest data, in bytes.
+    pub max_value_len: Option<usize>,
+    /// insert compresses data longer than this, get_data and the reads built
+    /// on it decompress it again. Needs the compression feature.
+    pub compress_values_over: Option<usize>,
+    /// How many writes an UndoStore made with this config can undo.
+    pub undo_log_len: usize,
+    /// Entries an LruStore made with this config keeps per group.
+    pub max_entries_per_group: Option<usize>,
+    /// Bytes of data_names and data an LruStore made with this config keeps
+    /// over all its groups.
+    pub max_total_bytes: Option<usize>,
+}
+
+impl Default for DbConfig {
+    fn default() -> DbConfig {
+        DbConfig {
+            max_key_len: usize::MAX,
+            allow_empty_keys: true,
+            max_value_len: None,
+            compress_values_over: None,
+            undo_log_len: 100,
+            max_entries_per_group: None,
+            max_total_bytes: None,
+        }
+    }
+}
+
+impl DbConfig {
+    /// InvalidKey when a group name or data_name breaks the limits.
+    pub fn check_key(&self, key: &str) -> Result<(), Error> {
+        if key.is_empty() && !self.allow_empty_keys {
+            return Err(Error::new(
+                ErrorKind::InvalidKey,
+                "empty group and data names are not allowed".to_string(),
+            ));
+        }
+        if key.len() > self.max_key_len {
+            return Err(Error::new(
+                ErrorKind::InvalidKey,
+                format!(
+                    "name '{}' is {} bytes, the limit is {}",
+                    key.chars().take(32).collect::<String>(),
+                    key.len(),
+                    self.max_key_len
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// ValueTooLarge when data is longer than max_value_len.
+    pub fn check_value(&self, group_name: &str, data_name: &str, data: &str) -> Result<(), Error> {
+        match self.max_value_len {
+            Some(max_value_len) if data.len() > max_value_len => Err(Error::new(
+                ErrorKind::ValueTooLarge,
+                format!(
+                    "'{}' in group '{}' is {} bytes, the limit is {}",
+                    data_name,
+                    group_name,
+                    data.len(),
+                    max_value_len
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every group of db, the name group included since its metadata
+    /// keys are written like entries. The metadata kept about entries and
+    /// groups is skipped, its keys are longer than the names they describe
+    /// and insert never checked them.
+    pub fn validate(&self, db: &Db) -> Result<(), Error> {
+        let mut group_names: Vec<&String> = db.keys().collect();
+        group_names.sort();
+        for group_name in group_names {
+            self.check_key(group_name)?;
+            let mut entries: Vec<(&String, &String)> = db[group_name]
+                .iter()
+                .filter(|(key, _)| {
+                    group_name != "name" || !(is_entry_meta_key(key) || is_keep_key(key))
+                })
+                .collect();
+            entries.sort();
+            for (data_name, data) in entries {
+                self.check_key(data_name)?;
+                self.check_value(group_name, data_name, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts the entry only if it is within the limits, db is left as it
+    /// was otherwise. The limits apply to data before it is compressed.
+    pub fn insert(
+        &self,
+        db: &mut Db,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<(), Error> {
+        self.check_key(group_name)?;
+        self.check_key(data_name)?;
+        self.check_value(group_name, data_name, &data)?;
+        let original_len = data.len();
+        let (data, compressed) = match self.compress_values_over {
+            Some(compress_over) if data.len() > compress_over => (compress_text(&data)?, true),
+            _ => (data, false),
+        };
+        insert_data(db, group_name, data_name, data, true)?;
+        // The length it had lets db_stats report it without decompressing.
+        let mark = compressed.then(|| format!("zlib:{}", original_len));
+        set_entry_meta_value(db, "compressed", group_name, data_name, mark);
+        Ok(())
+    }
+}
+
+/// The entry's data, decompressed when DbConfig::insert compressed it. Kept
+/// for callers that want an owned String, get_data decompresses too.
+pub fn read_decompressed(db: &Db, group_name: &str, data_name: &str) -> Result<String, Error> {
+    get_data(db, group_name, data_name).map(Cow::into_owned)
+}
+
+// data as the application stored it. The name group marks which entries are
+// compressed, so data stored before compression existed is returned as is.
+pub(crate) fn stored_data<'a>(
+    db: &Db,
+    group_name: &str,
+    data_name: &str,
+    data: &'a str,
+) -> Result<Cow<'a, str>, Error> {
+    match entry_meta_value(db, "compressed", group_name, data_name) {
+        Some(_) => decompress_text(data).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(data)),
+    }
+}
+
+/// from_plain_text, then a validate so a file can't bring in entries the
+/// config would have refused.
+pub fn from_plain_text_with_config(contents: &str, config: &DbConfig) -> Result<Db, Error> {
+    let db = from_plain_text(contents)?;
+    config.validate(&db)?;
+    Ok(db)
+}
@@ -0,0 +1,28 @@
+use crate::db::get_group;
+use crate::error::Error;
+use crate::Db;
+
+/// One data_name,data row per entry of the group, sorted by data_name.
+pub fn export_group_csv(db: &Db, group_name: &str) -> Result<String, Error> {
+    let group = get_group(db, group_name)?;
+    let mut entries: Vec<(&String, &String)> = group.iter().collect();
+    entries.sort();
+    let mut csv = String::from("data_name,data\r\n");
+    for (data_name, data) in entries {
+        csv.push_str(&csv_field(data_name));
+        csv.push(',');
+        csv.push_str(&csv_field(data));
+        csv.push_str("\r\n");
+    }
+    Ok(csv)
+}
+
+// RFC 4180: fields containing commas, quotes or line breaks are quoted and
+// quotes inside them doubled.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,1030 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config::stored_data;
+use crate::error::{Error, ErrorKind};
+use crate::file::{atomic_save, load_from_file};
+use crate::format::{
+    crc32, escape_db_name, from_plain_text, to_plain_text, CHECKSUM_PREFIX, FORMAT_MAGIC,
+    FORMAT_VERSION,
+};
+use crate::history::{history_after_write, history_before_write};
+use crate::timestamps::{
+    copy_entry_metadata, copy_group_metadata, expired_error, forget_all_entry_metadata, forget_entry_metadata,
+    forget_group_metadata, is_expired, move_entry_metadata, record_write,
+};
+use crate::tombstone::{apply_tombstone, modified_time, tombstone_time, tombstones};
+use crate::Db;
+
+static TEMPLATE_DB: &str = "\ntemp_group\ntemp,true\n";
+
+//fn is_instance<T>(_: &T) -> String{
+//    return std::any::type_name::<T>().to_owned();
+//}
+
+/// Creates `<db_name>.pdbr` holding a template group, panicking if it can't be
+/// written.
+pub fn create_db(db_name: &str) {
+    let header = format!("{} {}\n", FORMAT_MAGIC, FORMAT_VERSION);
+    let escaped_name = escape_db_name(db_name);
+    let db: Vec<&str> = vec![&header, &escaped_name, TEMPLATE_DB];
+    let mut db_str: String = db.into_iter().collect();
+    let crc = crc32(db_str.as_bytes());
+    db_str.push_str(&format!("{}{:08x}\n", CHECKSUM_PREFIX, crc));
+    let db_file_name: String = format!("{}.pdbr", db_name);
+    let path = Path::new(&db_file_name);
+    let display = path.display();
+    let mut file: File = match File::create(path) {
+        Err(why) => panic!("couldn't create file {}: {}", display, why),
+        Ok(file) => file,
+    };
+
+    if let Err(why) = file.write_all(db_str.as_bytes()) {
+        panic!("couldn't write to file {}: {}", display, why)
+    }
+}
+
+/// Saves db to `<name>.pdbr` with [`atomic_save`], panicking on failure.
+pub fn write_db(db: HashMap<String,HashMap<String,String>>) {
+    if let Err(why) = atomic_save(&db, db_file_name(&db)) {
+        panic!("couldn't write to file {}", why)
+    }
+}
+
+// <name>.pdbr, or default.pdbr when the name group has no name.
+fn db_file_name(db: &Db) -> String {
+    match db.get("name").and_then(|inner| inner.get("name")) {
+        Some(value) => format!("{}.pdbr", value),
+        None => "default.pdbr".to_string(),
+    }
+}
+
+/// Loads `<db_name>.pdbr`, panicking on failure.
+pub fn load_db(db_name: &str) -> HashMap<String, HashMap<String, String>> {
+    let file_path: String = format!("{}.pdbr", db_name);
+    let mut db: Db = match load_from_file(file_path) {
+        Err(why) => panic!("couldn't load database {}", why),
+        Ok(db) => db,
+    };
+    // Only the name, the rest of the name group is the file's metadata.
+    db.entry("name".to_string())
+        .or_default()
+        .insert("name".to_string(), db_name.to_string());
+    db
+}
+
+/// The data stored under data_name in group_name.
+pub fn read_data(db: HashMap<String,HashMap<String,String>>,group_name: String, data_name: String) -> Result<String, Error> {
+    get_data(&db, &group_name, &data_name).map(Cow::into_owned)
+}
+
+/// Stores data under data_name in group_name, creating the group if needed,
+/// and saves the database like [`write_db`], returning the error instead of
+/// panicking.
+pub fn append_data(mut db: HashMap<String,HashMap<String,String>>,group_name: String, data_name: String, data: String) -> Result<(), Error> {
+    insert_data(&mut db, &group_name, &data_name, data, true)?;
+    atomic_save(&db, db_file_name(&db))
+}
+
+/// Stores every entry in group_name and saves once, so the file never holds
+/// only some of them.
+pub fn append_batch<T: fmt::Display>(mut db: Db, group_name: &str, entries: &[(&str, T)]) -> Result<(), Error> {
+    for (data_name, data) in entries {
+        insert_data(&mut db, group_name, data_name, data.to_string(), true)?;
+    }
+    atomic_save(&db, db_file_name(&db))
+}
+
+/// True when the group was created, false when it was already there. The
+/// name group always counts as already there.
+pub fn create_group(db: &mut Db, group_name: &str) -> bool {
+    if db.contains_key(group_name) || group_name == "name" {
+        return false;
+    }
+    db.insert(group_name.to_string(), HashMap::new());
+    true
+}
+
+/// Stores data in memory, returning what was stored under data_name before.
+/// A missing group is created when create_group is set and is GroupNotFound
+/// otherwise, which catches misspelt group names.
+pub fn insert_data(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    data: String,
+    create_group: bool,
+) -> Result<Option<String>, Error> {
+    if !create_group || group_name == "name" {
+        get_group(db, group_name)?;
+    }
+    let replaced = history_before_write(db, group_name, data_name)?;
+    let group = db.entry(group_name.to_string()).or_default();
+    let previous = group.insert(data_name.to_string(), data);
+    record_write(db, group_name, data_name);
+    if let Some(replaced) = replaced {
+        history_after_write(db, group_name, data_name, replaced);
+    }
+    Ok(previous)
+}
+
+/// Stores data only when data_name isn't in the group yet, returning whether
+/// it did. An existing entry is left untouched, use insert_data to replace it
+/// and get the old data back. An expired entry counts as missing.
+pub fn insert_if_absent<T: fmt::Display>(db: &mut Db, group_name: &str, data_name: &str, data: &T) -> Result<bool, Error> {
+    get_group(db, group_name)?;
+    if contains_data(db, group_name, data_name) {
+        return Ok(false);
+    }
+    insert_data(db, group_name, data_name, data.to_string(), false)?;
+    Ok(true)
+}
+
+/// The name group is not a real group and is never returned.
+pub fn get_group<'a>(db: &'a Db, group_name: &str) -> Result<&'a HashMap<String, String>, Error> {
+    match db.get(group_name) {
+        Some(group) if group_name != "name" => Ok(group),
+        _ => Err(Error::new(
+            ErrorKind::GroupNotFound,
+            format!("group '{}' does not exist", group_name),
+        )),
+    }
+}
+
+/// GroupNotFound when the group is missing, DataNotFound when only the entry
+/// is or it has expired. Data DbConfig::insert compressed comes back
+/// decompressed, which is the only time it is copied.
+pub fn get_data<'a>(db: &'a Db, group_name: &str, data_name: &str) -> Result<Cow<'a, str>, Error> {
+    let data = get_group(db, group_name)?.get(data_name).ok_or_else(|| {
+        Error::new(
+            ErrorKind::DataNotFound,
+            format!("'{}' does not exist in group '{}'", data_name, group_name),
+        )
+    })?;
+    if is_expired(db, group_name, data_name) {
+        return Err(expired_error(group_name, data_name));
+    }
+    stored_data(db, group_name, data_name, data)
+}
+
+/// The removed data, or None when there was nothing to remove. Entries of the
+/// name group are never removed.
+pub fn remove_data_entry(db: &mut Db, group_name: &str, data_name: &str) -> Option<String> {
+    if group_name == "name" {
+        return None;
+    }
+    let removed = db.get_mut(group_name)?.remove(data_name)?;
+    forget_entry_metadata(db, group_name, Some(data_name));
+    Some(removed)
+}
+
+/// How many entries the removed group had, or None when it didn't exist. The
+/// name group is never removed.
+pub fn remove_group(db: &mut Db, group_name: &str) -> Option<usize> {
+    if group_name == "name" {
+        return None;
+    }
+    let removed = db.remove(group_name)?.len();
+    forget_group_metadata(db, group_name);
+    Some(removed)
+}
+
+/// Every group name except name, sorted.
+pub fn list_groups(db: &Db) -> Vec<String> {
+    let mut group_names: Vec<String> = db.keys().filter(|key| *key != "name").cloned().collect();
+    group_names.sort();
+    group_names
+}
+
+/// The group's data_names, sorted. Expired entries are left out, like get_data
+/// leaves them out.
+pub fn list_data_names(db: &Db, group_name: &str) -> Result<Vec<String>, Error> {
+    let mut data_names: Vec<String> = get_group(db, group_name)?
+        .keys()
+        .filter(|data_name| !is_expired(db, group_name, data_name))
+        .cloned()
+        .collect();
+    data_names.sort();
+    Ok(data_names)
+}
+
+/// One page of a group, see list_data_names_page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as after to get the next page, None on the last one.
+    pub next_after: Option<String>,
+}
+
+/// Up to limit of the group's data_names that sort after after, in order.
+/// The cursor is a data_name, so entries added or removed between pages
+/// can't make one repeat or be skipped, entries added before the cursor
+/// are just not seen.
+pub fn list_data_names_page(
+    db: &Db,
+    group_name: &str,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<Page<String>, Error> {
+    let mut data_names: Vec<&String> = get_group(db, group_name)?
+        .keys()
+        .filter(|data_name| after.is_none_or(|after| data_name.as_str() > after))
+        .filter(|data_name| !is_expired(db, group_name, data_name))
+        .collect();
+    data_names.sort();
+    // A limit of 0 leaves the cursor where it was.
+    let next_after = if data_names.len() <= limit {
+        None
+    } else if limit == 0 {
+        after.map(str::to_string)
+    } else {
+        Some(data_names[limit - 1].clone())
+    };
+    data_names.truncate(limit);
+    Ok(Page {
+        items: data_names.into_iter().cloned().collect(),
+        next_after,
+    })
+}
+
+/// list_data_names_page with each entry parsed as T.
+pub fn read_page<T: FromStr>(
+    db: &Db,
+    group_name: &str,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<Page<(String, T)>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let page = list_data_names_page(db, group_name, after, limit)?;
+    let items = page
+        .items
+        .into_iter()
+        .map(|data_name| {
+            let value = read_as::<T>(db, group_name, &data_name)?;
+            Ok((data_name, value))
+        })
+        .collect::<Result<Vec<(String, T)>, Error>>()?;
+    Ok(Page {
+        items,
+        next_after: page.next_after,
+    })
+}
+
+/// False for the name group, like get_group.
+pub fn contains_group(db: &Db, group_name: &str) -> bool {
+    get_group(db, group_name).is_ok()
+}
+
+/// False for entries that have expired, like get_data.
+pub fn contains_data(db: &Db, group_name: &str, data_name: &str) -> bool {
+    get_data(db, group_name, data_name).is_ok()
+}
+
+/// How many groups there are, not counting name.
+pub fn group_count(db: &Db) -> usize {
+    db.keys().filter(|key| *key != "name").count()
+}
+
+/// How many entries the group has, GroupNotFound when it is missing.
+pub fn data_count(db: &Db, group_name: &str) -> Result<usize, Error> {
+    Ok(get_group(db, group_name)?.len())
+}
+
+/// True when there are no groups besides name.
+pub fn is_empty(db: &Db) -> bool {
+    group_count(db) == 0
+}
+
+/// Entries across every group, not counting the name group's.
+pub fn total_entries(db: &Db) -> usize {
+    db.iter()
+        .filter(|(group_name, _)| *group_name != "name")
+        .map(|(_, group)| group.len())
+        .sum()
+}
+
+/// Empties the group but keeps it, returning how many entries it had.
+pub fn clear_group(db: &mut Db, group_name: &str) -> Result<usize, Error> {
+    let removed = data_count(db, group_name)?;
+    if let Some(group) = db.get_mut(group_name) {
+        group.clear();
+    }
+    forget_entry_metadata(db, group_name, None);
+    Ok(removed)
+}
+
+/// Removes every group, the name group and so the name and metadata stay.
+pub fn clear(db: &mut Db) {
+    db.retain(|group_name, _| group_name == "name");
+    forget_all_entry_metadata(db);
+}
+
+/// Removes every group not in keep, the name group always stays.
+pub fn retain_groups(db: &mut Db, keep: &[&str]) {
+    let removed: Vec<String> = db
+        .keys()
+        .filter(|group_name| *group_name != "name" && !keep.contains(&group_name.as_str()))
+        .cloned()
+        .collect();
+    for group_name in removed {
+        db.remove(&group_name);
+        forget_group_metadata(db, &group_name);
+    }
+}
+
+/// Moves a group to a new name. An existing group under that name is a
+/// Conflict unless overwrite is set, then it is replaced.
+pub fn rename_group(db: &mut Db, from: &str, to: &str, overwrite: bool) -> Result<(), Error> {
+    get_group(db, from)?;
+    if from == to {
+        return Ok(());
+    }
+    check_group_target(db, to, overwrite)?;
+    let group = db.remove(from).unwrap_or_default();
+    db.insert(to.to_string(), group);
+    copy_group_metadata(db, from, to, true);
+    Ok(())
+}
+
+/// Moves an entry to a new data_name in the same group, with the same
+/// overwrite rule as rename_group.
+pub fn rename_data(
+    db: &mut Db,
+    group_name: &str,
+    from: &str,
+    to: &str,
+    overwrite: bool,
+) -> Result<(), Error> {
+    get_data(db, group_name, from)?;
+    if from == to {
+        return Ok(());
+    }
+    let group = db.get_mut(group_name).expect("group was just found");
+    if group.contains_key(to) && !overwrite {
+        return Err(Error::new(
+            ErrorKind::Conflict,
+            format!("'{}' already exists in group '{}'", to, group_name),
+        ));
+    }
+    let data = group.remove(from).unwrap_or_default();
+    group.insert(to.to_string(), data);
+    move_entry_metadata(db, group_name, from, to);
+    Ok(())
+}
+
+/// Copies every entry of src into a new group dst, returning how many were
+/// copied. An existing dst is a Conflict unless overwrite is set.
+pub fn copy_group(db: &mut Db, src: &str, dst: &str, overwrite: bool) -> Result<usize, Error> {
+    let group = get_group(db, src)?.clone();
+    check_group_target(db, dst, overwrite)?;
+    let copied = group.len();
+    db.insert(dst.to_string(), group);
+    copy_group_metadata(db, src, dst, false);
+    Ok(copied)
+}
+
+/// Like copy_group, but every entry is parsed as T and passed through f,
+/// entries it returns None for are left out. Every entry is parsed and mapped
+/// before dst is written, so a parse error leaves db unchanged.
+pub fn copy_group_map<T, U>(
+    db: &mut Db,
+    src: &str,
+    dst: &str,
+    overwrite: bool,
+    f: impl Fn(&str, T) -> Option<U>,
+) -> Result<usize, Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    U: fmt::Display,
+{
+    let parsed: HashMap<String, T> = snapshot_group(db, src)?;
+    check_group_target(db, dst, overwrite)?;
+    let group: HashMap<String, String> = parsed
+        .into_iter()
+        .filter_map(|(data_name, value)| {
+            let mapped = f(&data_name, value)?;
+            Some((data_name, mapped.to_string()))
+        })
+        .collect();
+    let copied = group.len();
+    let data_names: Vec<String> = group.keys().cloned().collect();
+    db.insert(dst.to_string(), group);
+    forget_entry_metadata(db, dst, None);
+    for data_name in data_names {
+        record_write(db, dst, &data_name);
+    }
+    Ok(copied)
+}
+
+// A group may be written under dst when dst isn't name and either doesn't
+// exist yet or overwrite is set.
+fn check_group_target(db: &Db, dst: &str, overwrite: bool) -> Result<(), Error> {
+    if dst == "name" {
+        return Err(Error::new(
+            ErrorKind::Conflict,
+            "'name' is reserved and cannot be a group".to_string(),
+        ));
+    }
+    if db.contains_key(dst) && !overwrite {
+        return Err(Error::new(
+            ErrorKind::Conflict,
+            format!("group '{}' already exists", dst),
+        ));
+    }
+    Ok(())
+}
+
+/// Parses every entry of a group as T, failing on the first data_name (in
+/// sorted order) that doesn't parse.
+pub fn snapshot_group<T: FromStr>(db: &Db, group_name: &str) -> Result<HashMap<String, T>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let entries = sorted_entries(db, group_name)?;
+    let mut parsed = HashMap::with_capacity(entries.len());
+    for (data_name, data) in entries {
+        let value = data
+            .parse::<T>()
+            .map_err(|why| parse_error(group_name, data_name, &data, why))?;
+        parsed.insert(data_name.clone(), value);
+    }
+    Ok(parsed)
+}
+
+/// Calls f with every entry of the group, sorted by data_name.
+pub fn for_each_entry<F: FnMut(&str, &str)>(db: &Db, group_name: &str, mut f: F) -> Result<(), Error> {
+    for (data_name, data) in sorted_entries(db, group_name)? {
+        f(data_name, &data);
+    }
+    Ok(())
+}
+
+// The group's entries sorted by data_name, with their data as get_data would
+// return it and without the expired ones.
+fn sorted_entries<'a>(db: &'a Db, group_name: &str) -> Result<Vec<(&'a String, Cow<'a, str>)>, Error> {
+    let mut entries: Vec<(&String, &String)> = get_group(db, group_name)?
+        .iter()
+        .filter(|(data_name, _)| !is_expired(db, group_name, data_name))
+        .collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .map(|(data_name, data)| Ok((data_name, stored_data(db, group_name, data_name, data)?)))
+        .collect()
+}
+
+/// Every entry of the group parsed as T, sorted by data_name. Entries that
+/// don't parse are left out when skip_invalid is set and fail the read
+/// otherwise.
+pub fn read_all<T: FromStr>(db: &Db, group_name: &str, skip_invalid: bool) -> Result<Vec<(String, T)>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let entries = sorted_entries(db, group_name)?;
+    let mut parsed = Vec::with_capacity(entries.len());
+    for (data_name, data) in entries {
+        match data.parse::<T>() {
+            Ok(value) => parsed.push((data_name.clone(), value)),
+            Err(_) if skip_invalid => {}
+            Err(why) => return Err(parse_error(group_name, data_name, &data, why)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// The group's data_names starting with prefix, sorted.
+pub fn data_names_with_prefix(db: &Db, group_name: &str, prefix: &str) -> Result<Vec<String>, Error> {
+    let mut data_names = list_data_names(db, group_name)?;
+    data_names.retain(|data_name| data_name.starts_with(prefix));
+    Ok(data_names)
+}
+
+/// The group's data_names matching a glob pattern, sorted. `*` matches any
+/// run of characters and `?` exactly one, there is no escaping.
+pub fn data_names_matching(db: &Db, group_name: &str, pattern: &str) -> Result<Vec<String>, Error> {
+    let mut data_names = list_data_names(db, group_name)?;
+    data_names.retain(|data_name| glob_match(pattern, data_name));
+    Ok(data_names)
+}
+
+/// Every entry whose data_name starts with prefix parsed as T, sorted by
+/// data_name.
+pub fn read_prefix<T: FromStr>(db: &Db, group_name: &str, prefix: &str) -> Result<Vec<(String, T)>, Error>
+where
+    T::Err: fmt::Display,
+{
+    data_names_with_prefix(db, group_name, prefix)?
+        .into_iter()
+        .map(|data_name| {
+            let value = read_as::<T>(db, group_name, &data_name)?;
+            Ok((data_name, value))
+        })
+        .collect()
+}
+
+/// Removes every entry whose data_name starts with prefix, returning how many
+/// there were.
+pub fn remove_prefix(db: &mut Db, group_name: &str, prefix: &str) -> Result<usize, Error> {
+    get_group(db, group_name)?;
+    let group = db.get_mut(group_name).expect("group was just found");
+    let removed: Vec<String> = group
+        .keys()
+        .filter(|data_name| data_name.starts_with(prefix))
+        .cloned()
+        .collect();
+    for data_name in &removed {
+        group.remove(data_name);
+    }
+    for data_name in &removed {
+        forget_entry_metadata(db, group_name, Some(data_name));
+    }
+    Ok(removed.len())
+}
+
+/// The entries of the group parsed as T that pred accepts, sorted by
+/// data_name. skip_invalid works like it does for read_all.
+pub fn find<T: FromStr, P: Fn(&str, &T) -> bool>(
+    db: &Db,
+    group_name: &str,
+    skip_invalid: bool,
+    pred: P,
+) -> Result<Vec<(String, T)>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let mut found = read_all::<T>(db, group_name, skip_invalid)?;
+    found.retain(|(data_name, value)| pred(data_name, value));
+    Ok(found)
+}
+
+/// How many entries find would return.
+pub fn count_where<T: FromStr, P: Fn(&str, &T) -> bool>(
+    db: &Db,
+    group_name: &str,
+    skip_invalid: bool,
+    pred: P,
+) -> Result<usize, Error>
+where
+    T::Err: fmt::Display,
+{
+    Ok(find(db, group_name, skip_invalid, pred)?.len())
+}
+
+/// Removes the entries find would return, returning how many there were.
+/// Nothing is removed when an entry fails to parse without skip_invalid.
+pub fn remove_where<T: FromStr, P: Fn(&str, &T) -> bool>(
+    db: &mut Db,
+    group_name: &str,
+    skip_invalid: bool,
+    pred: P,
+) -> Result<usize, Error>
+where
+    T::Err: fmt::Display,
+{
+    let found = find(db, group_name, skip_invalid, pred)?;
+    for (data_name, _) in &found {
+        remove_data_entry(db, group_name, data_name);
+    }
+    Ok(found.len())
+}
+
+// Only ever backtracks to the last *, which is enough when * is the only
+// wildcard that can match more than one character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match last_star {
+                Some((star_p, star_t)) => {
+                    last_star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Stores value as `<type name>:<value>` so read_tagged can tell when it is
+/// read back as a different type. get_data returns the tagged text as is.
+/// The group is checked and the old data returned like insert_data.
+pub fn insert_tagged<T: fmt::Display>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    value: &T,
+    create_group: bool,
+) -> Result<Option<String>, Error> {
+    let tagged = format!("{}:{}", std::any::type_name::<T>(), value);
+    insert_data(db, group_name, data_name, tagged, create_group)
+}
+
+/// TypeMismatch when the value wasn't stored by insert_tagged with the same T,
+/// which includes values written before tags existed.
+pub fn read_tagged<T: FromStr>(db: &Db, group_name: &str, data_name: &str) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    let data = get_data(db, group_name, data_name)?;
+    let type_name = std::any::type_name::<T>();
+    let Some(value) = data.strip_prefix(type_name).and_then(|rest| rest.strip_prefix(':')) else {
+        return Err(Error::new(
+            ErrorKind::TypeMismatch,
+            format!("'{}' in group '{}' was not stored as {}", data_name, group_name, type_name),
+        ));
+    };
+    value
+        .parse::<T>()
+        .map_err(|why| parse_error(group_name, data_name, value, why))
+}
+
+/// The entry parsed as T.
+pub fn read_as<T: FromStr>(db: &Db, group_name: &str, data_name: &str) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    let data = get_data(db, group_name, data_name)?;
+    data.parse::<T>()
+        .map_err(|why| parse_error(group_name, data_name, &data, why))
+}
+
+/// Each of data_names parsed as T, in the same order, None for the ones the
+/// group doesn't have. A missing group is still an error.
+pub fn read_batch<T: FromStr>(db: &Db, group_name: &str, data_names: &[&str]) -> Result<Vec<Option<T>>, Error>
+where
+    T::Err: fmt::Display,
+{
+    get_group(db, group_name)?;
+    data_names
+        .iter()
+        .map(|data_name| read_optional(db, group_name, data_name))
+        .collect()
+}
+
+/// The entry parsed as T, or default when the group doesn't have it. A missing
+/// group or data that doesn't parse is still an error.
+pub fn read_or<T: FromStr>(db: &Db, group_name: &str, data_name: &str, default: T) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    Ok(read_optional(db, group_name, data_name)?.unwrap_or(default))
+}
+
+/// Like read_or, but a missing entry is stored as the value f makes, which
+/// is then returned.
+pub fn read_or_insert_with<T>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    f: impl FnOnce() -> T,
+) -> Result<T, Error>
+where
+    T: FromStr + fmt::Display,
+    T::Err: fmt::Display,
+{
+    if let Some(value) = read_optional(db, group_name, data_name)? {
+        return Ok(value);
+    }
+    let value = f();
+    insert_data(db, group_name, data_name, value.to_string(), false)?;
+    Ok(value)
+}
+
+/// Removes the entry and returns it parsed as T. Data that doesn't parse is
+/// left in place and the error returned.
+pub fn take_data<T: FromStr>(db: &mut Db, group_name: &str, data_name: &str) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    let value = read_as(db, group_name, data_name)?;
+    remove_data_entry(db, group_name, data_name);
+    Ok(value)
+}
+
+/// take_data for the first data_name of the group in sorted order, None when
+/// the group is empty.
+pub fn take_any<T: FromStr>(db: &mut Db, group_name: &str) -> Result<Option<(String, T)>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let Some(data_name) = list_data_names(db, group_name)?.into_iter().next() else {
+        return Ok(None);
+    };
+    let value = take_data(db, group_name, &data_name)?;
+    Ok(Some((data_name, value)))
+}
+
+/// Passes the entry parsed as T, or None when it's missing, to f and stores
+/// what f returns, removing the entry when that is None. Returns what ended
+/// up stored. Data that doesn't parse is an error and f isn't called.
+pub fn update_data<T, F>(db: &mut Db, group_name: &str, data_name: &str, f: F) -> Result<Option<T>, Error>
+where
+    T: FromStr + fmt::Display,
+    T::Err: fmt::Display,
+    F: FnOnce(Option<T>) -> Option<T>,
+{
+    let current = read_optional(db, group_name, data_name)?;
+    let updated = f(current);
+    store_optional(db, group_name, data_name, updated.as_ref())?;
+    Ok(updated)
+}
+
+/// Stores new only when the entry parsed as T equals expected, None meaning
+/// the entry is missing, returning whether it did. A new of None removes the
+/// entry.
+pub fn compare_and_swap<T>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    expected: Option<&T>,
+    new: Option<&T>,
+) -> Result<bool, Error>
+where
+    T: FromStr + fmt::Display + PartialEq,
+    T::Err: fmt::Display,
+{
+    let current: Option<T> = read_optional(db, group_name, data_name)?;
+    if current.as_ref() != expected {
+        return Ok(false);
+    }
+    store_optional(db, group_name, data_name, new)?;
+    Ok(true)
+}
+
+/// Adds delta to the entry, a missing entry counting as 0, and returns the
+/// new value. Data that isn't an integer, or a result that would overflow,
+/// is an error and the entry is left as it was.
+pub fn increment(db: &mut Db, group_name: &str, data_name: &str, delta: i64) -> Result<i64, Error> {
+    let current: i64 = read_optional(db, group_name, data_name)?.unwrap_or(0);
+    let Some(updated) = current.checked_add(delta) else {
+        return Err(Error::new(
+            ErrorKind::Overflow,
+            format!(
+                "adding {} to '{}' in group '{}' ({}) overflows",
+                delta, data_name, group_name, current
+            ),
+        ));
+    };
+    store_optional(db, group_name, data_name, Some(&updated))?;
+    Ok(updated)
+}
+
+/// increment for floating point data, which can't overflow, only become
+/// infinite.
+pub fn increment_f64(db: &mut Db, group_name: &str, data_name: &str, delta: f64) -> Result<f64, Error> {
+    let updated = read_optional::<f64>(db, group_name, data_name)?.unwrap_or(0.0) + delta;
+    store_optional(db, group_name, data_name, Some(&updated))?;
+    Ok(updated)
+}
+
+// The entry parsed as T, None when the group doesn't have it.
+pub(crate) fn read_optional<T: FromStr>(db: &Db, group_name: &str, data_name: &str) -> Result<Option<T>, Error>
+where
+    T::Err: fmt::Display,
+{
+    match get_data(db, group_name, data_name) {
+        Ok(data) => data
+            .parse::<T>()
+            .map(Some)
+            .map_err(|why| parse_error(group_name, data_name, &data, why)),
+        Err(why) if why.kind == ErrorKind::DataNotFound => Ok(None),
+        Err(why) => Err(why),
+    }
+}
+
+// Stores value, or removes the entry when it is None.
+pub(crate) fn store_optional<T: fmt::Display>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    value: Option<&T>,
+) -> Result<(), Error> {
+    match value {
+        Some(value) => {
+            insert_data(db, group_name, data_name, value.to_string(), false)?;
+        }
+        None => {
+            remove_data_entry(db, group_name, data_name);
+        }
+    }
+    Ok(())
+}
+
+// Says where the data came from and how long it was, a parse error on its
+// own rarely does.
+pub(crate) fn parse_error(group_name: &str, data_name: &str, data: &str, why: impl fmt::Display) -> Error {
+    Error::new(
+        ErrorKind::Parse,
+        format!(
+            "'{}' in group '{}' ({} bytes) could not be parsed: {}",
+            data_name,
+            group_name,
+            data.len(),
+            why
+        ),
+    )
+}
+
+/// Readable listing for debugging, groups and entries sorted:
+///
+/// ```text
+/// test
+///   temp_group
+///     temp = "true" (4 bytes)
+/// ```
+pub fn render_db(db: &Db) -> String {
+    let mut rendered = String::new();
+    if let Some(db_name) = db.get("name").and_then(|inner| inner.get("name")) {
+        rendered.push_str(db_name);
+        rendered.push('\n');
+    }
+    let mut group_names: Vec<&String> = db.keys().filter(|key| *key != "name").collect();
+    group_names.sort();
+    for group_name in group_names {
+        rendered.push_str(&format!("  {}\n", group_name));
+        let mut entries: Vec<(&String, &String)> = db[group_name].iter().collect();
+        entries.sort();
+        if entries.is_empty() {
+            rendered.push_str("    (empty)\n");
+        }
+        for (data_name, data) in entries {
+            rendered.push_str(&format!("    {} = {:?} ({} bytes)\n", data_name, data, data.len()));
+        }
+    }
+    rendered
+}
+
+/// What import_group does when the group is already in the database, and
+/// merge_from when an entry is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportConflict {
+    Replace,
+    KeepExisting,
+    Error,
+}
+
+/// A .pdbr file holding only this group, named after it.
+pub fn export_group(db: &Db, group_name: &str) -> Result<String, Error> {
+    let group = get_group(db, group_name)?;
+    let mut name_hash: HashMap<String, String> = HashMap::new();
+    name_hash.insert("name".to_string(), group_name.to_string());
+    let mut exported: Db = HashMap::new();
+    exported.insert("name".to_string(), name_hash);
+    exported.insert(group_name.to_string(), group.clone());
+    Ok(to_plain_text(&exported))
+}
+
+/// The export is parsed and checked in full before db is touched, so a bad
+/// export never leaves db half imported.
+pub fn import_group(
+    db: &mut Db,
+    group_name: &str,
+    exported: &str,
+    on_conflict: ImportConflict,
+) -> Result<(), Error> {
+    let group = parse_exported_group(group_name, exported)?;
+    if db.contains_key(group_name) {
+        match on_conflict {
+            ImportConflict::Replace => {}
+            ImportConflict::KeepExisting => return Ok(()),
+            ImportConflict::Error => {
+                return Err(Error::new(
+                    ErrorKind::Conflict,
+                    format!("group '{}' already exists", group_name),
+                ))
+            }
+        }
+    }
+    let data_names: Vec<String> = group.keys().cloned().collect();
+    db.insert(group_name.to_string(), group);
+    forget_entry_metadata(db, group_name, None);
+    for data_name in data_names {
+        record_write(db, group_name, &data_name);
+    }
+    Ok(())
+}
+
+/// The one group of an export_group file, checked but not yet in any db.
+pub(crate) fn parse_exported_group(
+    group_name: &str,
+    exported: &str,
+) -> Result<HashMap<String, String>, Error> {
+    let mut imported = from_plain_text(exported)?;
+    imported.remove("name");
+    if imported.len() != 1 {
+        return Err(Error::new(
+            ErrorKind::Corrupt,
+            format!("expected one exported group, found {}", imported.len()),
+        ));
+    }
+    if group_name == "name" {
+        return Err(Error::new(
+            ErrorKind::Conflict,
+            "'name' is reserved and cannot be a group".to_string(),
+        ));
+    }
+    Ok(imported.into_values().next().unwrap_or_default())
+}
+
+/// What merge_from changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeReport {
+    pub groups_created: usize,
+    pub entries_added: usize,
+    pub entries_overwritten: usize,
+    /// Entries kept as they were under ImportConflict::KeepExisting.
+    pub conflicts_skipped: usize,
+    /// Entries of other left out, or entries of db removed, because of a
+    /// newer tombstone on the other side.
+    pub tombstoned: usize,
+}
+
+/// Adds every group and entry of other to db. An entry both have with
+/// different data is a conflict, resolved by on_conflict. With
+/// ImportConflict::Error db is left unchanged and the error lists every
+/// conflicting entry. The name group of other is ignored except for its
+/// tombstones: a tombstone beats an entry last written before it, or with no
+/// modified time at all.
+pub fn merge_from(db: &mut Db, other: &Db, on_conflict: ImportConflict) -> Result<MergeReport, Error> {
+    let mut group_names: Vec<&String> = other.keys().filter(|key| *key != "name").collect();
+    group_names.sort();
+    if on_conflict == ImportConflict::Error {
+        let mut conflicts = Vec::new();
+        for group_name in &group_names {
+            let Some(group) = db.get(*group_name) else {
+                continue;
+            };
+            let mut entries: Vec<(&String, &String)> = other[*group_name].iter().collect();
+            entries.sort();
+            for (data_name, data) in entries {
+                if group.get(data_name).is_some_and(|existing| existing != data) {
+                    conflicts.push(format!("'{}' in group '{}'", data_name, group_name));
+                }
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(Error::new(
+                ErrorKind::Conflict,
+                format!("merge conflicts: {}", conflicts.join(", ")),
+            ));
+        }
+    }
+    let mut report = MergeReport::default();
+    for group_name in group_names {
+        if !db.contains_key(group_name) {
+            db.insert(group_name.clone(), HashMap::new());
+            report.groups_created += 1;
+        }
+        for (data_name, data) in &other[group_name] {
+            match db.get(group_name).and_then(|group| group.get(data_name)) {
+                None if tombstone_time(db, group_name, data_name)
+                    .is_some_and(|deleted| modified_time(other, group_name, data_name) < deleted) =>
+                {
+                    report.tombstoned += 1;
+                    continue;
+                }
+                None => report.entries_added += 1,
+                Some(existing) if existing == data => continue,
+                Some(_) if on_conflict == ImportConflict::KeepExisting => {
+                    report.conflicts_skipped += 1;
+                    continue;
+                }
+                Some(_) => report.entries_overwritten += 1,
+            }
+            db.get_mut(group_name)
+                .expect("group was just created")
+                .insert(data_name.clone(), data.clone());
+            copy_entry_metadata(db, other, group_name, data_name);
+        }
+    }
+    for (group_name, data_name, deleted) in tombstones(other) {
+        if contains_data(other, &group_name, &data_name) || group_name == "name" {
+            continue;
+        }
+        if apply_tombstone(db, &group_name, &data_name, deleted) {
+            report.tombstoned += 1;
+        }
+    }
+    Ok(report)
+}
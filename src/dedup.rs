@@ -0,0 +1,258 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{Error, ErrorKind};
+use crate::file::atomic_write;
+use crate::format::{from_plain_text, write_pooled};
+use crate::store::KvStore;
+use crate::timestamps::{forget_entry_metadata, record_write};
+use crate::Db;
+
+/// A database that keeps each distinct data once, shared by every entry that
+/// holds it, for databases where many entries store the same large value.
+/// Saved as plain text with each shared value written once.
+///
+/// Used through KvStore it behaves like a Db, get_shared reads without
+/// copying. Data only lives as long as some entry holds it.
+#[derive(Debug, Default)]
+pub struct DedupDb {
+    // Only the name group, so the Db functions for metadata work on it.
+    meta: Db,
+    groups: HashMap<String, HashMap<String, Arc<str>>>,
+    // Every distinct data by the hash of its content, with how many entries
+    // hold it. Counted apart from the Arc's strong count, get_shared hands
+    // out references that aren't entries.
+    pool: HashMap<u64, Vec<Pooled>>,
+}
+
+#[derive(Debug)]
+struct Pooled {
+    data: Arc<str>,
+    entries: usize,
+}
+
+fn content_hash(data: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl DedupDb {
+    /// Entries with the same data share it from the start.
+    pub fn from_db(mut db: Db) -> DedupDb {
+        let mut dedup = DedupDb::default();
+        if let Some(name_group) = db.remove("name") {
+            dedup.meta.insert("name".to_string(), name_group);
+        }
+        for (group_name, group) in db {
+            let mut shared = HashMap::with_capacity(group.len());
+            for (data_name, data) in group {
+                shared.insert(data_name, dedup.intern(data));
+            }
+            dedup.groups.insert(group_name, shared);
+        }
+        dedup
+    }
+
+    /// A plain Db with a copy of the data for every entry.
+    pub fn to_db(&self) -> Db {
+        let mut db = self.meta.clone();
+        for (group_name, group) in &self.groups {
+            let group = group
+                .iter()
+                .map(|(data_name, data)| (data_name.clone(), data.to_string()))
+                .collect();
+            db.insert(group_name.clone(), group);
+        }
+        db
+    }
+
+    /// Reads anything from_plain_text does. Entries with the same data share
+    /// it again, whether or not the file was written by a DedupDb.
+    pub fn from_plain_text(contents: &str) -> Result<DedupDb, Error> {
+        from_plain_text(contents).map(DedupDb::from_db)
+    }
+
+    /// Like to_plain_text, with data held by more than one entry written once.
+    pub fn to_plain_text(&self) -> String {
+        let mut bytes = Vec::new();
+        // Writing into a Vec can't fail and everything written is a &str.
+        self.write_plain_text(&mut bytes)
+            .expect("writing into memory failed");
+        String::from_utf8(bytes).expect("database text is UTF-8")
+    }
+
+    /// Like atomic_save.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        atomic_write(path.as_ref(), |writer| self.write_plain_text(writer))
+    }
+
+    /// The entry's data without copying it.
+    pub fn get_shared(&self, group_name: &str, data_name: &str) -> Result<Arc<str>, Error> {
+        self.group(group_name)?
+            .get(data_name)
+            .cloned()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::DataNotFound,
+                    format!("'{}' does not exist in group '{}'", data_name, group_name),
+                )
+            })
+    }
+
+    /// How many distinct data are kept.
+    pub fn pool_len(&self) -> usize {
+        self.pool.values().map(Vec::len).sum()
+    }
+
+    // Data already in the pool is shared and the new copy dropped.
+    fn intern(&mut self, data: String) -> Arc<str> {
+        let bucket = self.pool.entry(content_hash(&data)).or_default();
+        if let Some(pooled) = bucket.iter_mut().find(|pooled| *pooled.data == *data) {
+            pooled.entries += 1;
+            return pooled.data.clone();
+        }
+        let shared: Arc<str> = Arc::from(data);
+        bucket.push(Pooled {
+            data: shared.clone(),
+            entries: 1,
+        });
+        shared
+    }
+
+    // Drops an entry's reference, and the data with it when no entry holds
+    // it anymore.
+    fn release(&mut self, data: Arc<str>) {
+        let hash = content_hash(&data);
+        let Some(bucket) = self.pool.get_mut(&hash) else {
+            return;
+        };
+        let Some(i) = bucket
+            .iter()
+            .position(|pooled| Arc::ptr_eq(&pooled.data, &data))
+        else {
+            return;
+        };
+        bucket[i].entries -= 1;
+        if bucket[i].entries == 0 {
+            bucket.swap_remove(i);
+            if bucket.is_empty() {
+                self.pool.remove(&hash);
+            }
+        }
+    }
+
+    fn group(&self, group_name: &str) -> Result<&HashMap<String, Arc<str>>, Error> {
+        self.groups.get(group_name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::GroupNotFound,
+                format!("group '{}' does not exist", group_name),
+            )
+        })
+    }
+
+    // Data held by more than one entry goes into the pool, numbered in the
+    // order it is first written so the same database gives the same bytes.
+    fn write_plain_text<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let shared: HashMap<*const u8, usize> = self
+            .pool
+            .values()
+            .flatten()
+            .map(|pooled| (pooled.data.as_ptr(), pooled.entries))
+            .collect();
+        let mut group_names: Vec<&String> = self.groups.keys().collect();
+        group_names.sort();
+        let mut pool: Vec<&str> = Vec::new();
+        let mut pool_index: HashMap<*const u8, usize> = HashMap::new();
+        for group_name in group_names {
+            let mut entries: Vec<(&String, &Arc<str>)> = self.groups[group_name].iter().collect();
+            entries.sort_by_key(|(data_name, _)| *data_name);
+            for (_, data) in entries {
+                if shared.get(&data.as_ptr()) > Some(&1) {
+                    pool_index.entry(data.as_ptr()).or_insert_with(|| {
+                        pool.push(data);
+                        pool.len() - 1
+                    });
+                }
+            }
+        }
+        write_pooled(
+            self.meta.get("name"),
+            &self.groups,
+            &pool,
+            &|data: &Arc<str>| pool_index.get(&data.as_ptr()).copied(),
+            writer,
+        )
+    }
+}
+
+impl KvStore for DedupDb {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        if group_name == "name" || self.groups.contains_key(group_name) {
+            return false;
+        }
+        self.groups.insert(group_name.to_string(), HashMap::new());
+        true
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        if group_name == "name" {
+            self.group(group_name)?;
+        }
+        let data = self.intern(data);
+        let replaced = self
+            .groups
+            .entry(group_name.to_string())
+            .or_default()
+            .insert(data_name.to_string(), data);
+        record_write(&mut self.meta, group_name, data_name);
+        Ok(replaced.map(|replaced| {
+            let text = replaced.to_string();
+            self.release(replaced);
+            text
+        }))
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.get_shared(group_name, data_name)
+            .map(|data| data.to_string())
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let removed = self.groups.get_mut(group_name)?.remove(data_name)?;
+        forget_entry_metadata(&mut self.meta, group_name, Some(data_name));
+        let text = removed.to_string();
+        self.release(removed);
+        Some(text)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.groups.remove(group_name)?;
+        forget_entry_metadata(&mut self.meta, group_name, None);
+        let count = removed.len();
+        for data in removed.into_values() {
+            self.release(data);
+        }
+        Some(count)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        let mut group_names: Vec<String> = self.groups.keys().cloned().collect();
+        group_names.sort();
+        group_names
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        let mut data_names: Vec<String> = self.group(group_name)?.keys().cloned().collect();
+        data_names.sort();
+        Ok(data_names)
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Db;
+
+/// How other differs from db, see diff. Everything is sorted by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbDiff {
+    pub groups_only_in_db: Vec<String>,
+    pub groups_only_in_other: Vec<String>,
+    /// Groups both have that differ, groups that are the same are left out.
+    pub groups: Vec<GroupDiff>,
+}
+
+/// How other's copy of a group differs from db's.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupDiff {
+    pub group_name: String,
+    /// data_names only other has.
+    pub added: Vec<String>,
+    /// data_names only db has.
+    pub removed: Vec<String>,
+    /// data_name, db's data, other's data.
+    pub changed: Vec<(String, String, String)>,
+}
+
+impl DbDiff {
+    /// True when db and other have the same groups and entries.
+    pub fn is_empty(&self) -> bool {
+        self.groups_only_in_db.is_empty()
+            && self.groups_only_in_other.is_empty()
+            && self.groups.is_empty()
+    }
+}
+
+impl GroupDiff {
+    /// True when the group is the same in both.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// One line per difference, - for what only db has, + for what only other
+/// has and ~ for changed data.
+impl fmt::Display for DbDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for group_name in &self.groups_only_in_db {
+            writeln!(f, "- group {}", group_name)?;
+        }
+        for group_name in &self.groups_only_in_other {
+            writeln!(f, "+ group {}", group_name)?;
+        }
+        for group in &self.groups {
+            writeln!(f, "  group {}", group.group_name)?;
+            for data_name in &group.removed {
+                writeln!(f, "-   {}", data_name)?;
+            }
+            for data_name in &group.added {
+                writeln!(f, "+   {}", data_name)?;
+            }
+            for (data_name, before, after) in &group.changed {
+                writeln!(f, "~   {}: {:?} -> {:?}", data_name, before, after)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares every group but the name group.
+pub fn diff(db: &Db, other: &Db) -> DbDiff {
+    let mut result = DbDiff::default();
+    let mut group_names: Vec<&String> = db
+        .keys()
+        .chain(other.keys())
+        .filter(|key| *key != "name")
+        .collect();
+    group_names.sort();
+    group_names.dedup();
+    for group_name in group_names {
+        match (db.get(group_name), other.get(group_name)) {
+            (Some(_), None) => result.groups_only_in_db.push(group_name.clone()),
+            (None, Some(_)) => result.groups_only_in_other.push(group_name.clone()),
+            (Some(group), Some(other_group)) => {
+                let group_diff = diff_group(group_name, group, other_group);
+                if !group_diff.is_empty() {
+                    result.groups.push(group_diff);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    result
+}
+
+fn diff_group(
+    group_name: &str,
+    group: &HashMap<String, String>,
+    other_group: &HashMap<String, String>,
+) -> GroupDiff {
+    let mut group_diff = GroupDiff {
+        group_name: group_name.to_string(),
+        ..GroupDiff::default()
+    };
+    let mut data_names: Vec<&String> = group.keys().chain(other_group.keys()).collect();
+    data_names.sort();
+    data_names.dedup();
+    for data_name in data_names {
+        match (group.get(data_name), other_group.get(data_name)) {
+            (Some(_), None) => group_diff.removed.push(data_name.clone()),
+            (None, Some(_)) => group_diff.added.push(data_name.clone()),
+            (Some(data), Some(other_data)) if data != other_data => {
+                group_diff
+                    .changed
+                    .push((data_name.clone(), data.clone(), other_data.clone()))
+            }
+            _ => {}
+        }
+    }
+    group_diff
+}
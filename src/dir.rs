@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::db::{export_group, parse_exported_group};
+use crate::error::{Error, ErrorKind};
+use crate::file::{atomic_save, atomic_write, load_from_file, remove_if_exists};
+use crate::Db;
+
+// Directory layout: db.meta holds the database name and metadata, and every
+// group is its own <group>.pdbr written by export_group.
+pub(crate) const DIR_META_FILE: &str = "db.meta";
+
+// Anything but ASCII letters, digits, '-' and '_' becomes %XX, which rules
+// out '/', '..' and '.pdbr' inside a name. A name that is a reserved Windows
+// device name also gets its first letter encoded.
+fn group_file_stem(group_name: &str) -> String {
+    let mut stem = String::with_capacity(group_name.len());
+    for byte in group_name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            stem.push(byte as char);
+        } else {
+            stem.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    let reserved = ["CON", "PRN", "AUX", "NUL"];
+    let upper = stem.to_ascii_uppercase();
+    let is_reserved = reserved.contains(&upper.as_str())
+        || (upper.len() == 4
+            && (upper.starts_with("COM") || upper.starts_with("LPT"))
+            && upper.as_bytes()[3].is_ascii_digit());
+    if is_reserved {
+        stem = format!("%{:02X}{}", stem.as_bytes()[0], &stem[1..]);
+    }
+    stem
+}
+
+fn group_name_from_stem(stem: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(stem.len());
+    let mut rest = stem.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Files of groups that are no longer in db are deleted.
+pub fn save_to_dir(db: &Db, dir: impl AsRef<Path>) -> Result<(), Error> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(|why| Error::io(dir, why))?;
+    write_meta_file(db, dir)?;
+    for group_name in db.keys().filter(|key| *key != "name") {
+        write_group_file(db, dir, group_name)?;
+    }
+    for (group_name, path) in group_files(dir)? {
+        if !db.contains_key(&group_name) || group_name == "name" {
+            remove_if_exists(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Only rewrites the files of groups that differ from on_disk, the database
+/// as the directory last held it, and deletes the files of groups on_disk has
+/// but db doesn't. on_disk is brought up to date as files are written, and the
+/// groups whose files were written or deleted are returned, sorted.
+///
+/// Start on_disk from load_from_dir, or from a clone of db after save_to_dir.
+pub fn save_changed_to_dir(db: &Db, dir: impl AsRef<Path>, on_disk: &mut Db) -> Result<Vec<String>, Error> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(|why| Error::io(dir, why))?;
+    if db.get("name") != on_disk.get("name") {
+        write_meta_file(db, dir)?;
+        on_disk.insert("name".to_string(), db.get("name").cloned().unwrap_or_default());
+    }
+    let mut changed = Vec::new();
+    for (group_name, group) in db.iter().filter(|(key, _)| *key != "name") {
+        if on_disk.get(group_name) != Some(group) {
+            write_group_file(db, dir, group_name)?;
+            on_disk.insert(group_name.clone(), group.clone());
+            changed.push(group_name.clone());
+        }
+    }
+    let removed: Vec<String> = on_disk
+        .keys()
+        .filter(|key| *key != "name" && !db.contains_key(*key))
+        .cloned()
+        .collect();
+    for group_name in removed {
+        remove_if_exists(&group_file_path(dir, &group_name))?;
+        on_disk.remove(&group_name);
+        changed.push(group_name);
+    }
+    changed.sort();
+    Ok(changed)
+}
+
+fn write_meta_file(db: &Db, dir: &Path) -> Result<(), Error> {
+    let mut meta: Db = HashMap::new();
+    meta.insert("name".to_string(), db.get("name").cloned().unwrap_or_default());
+    atomic_save(&meta, dir.join(DIR_META_FILE))
+}
+
+fn write_group_file(db: &Db, dir: &Path, group_name: &str) -> Result<(), Error> {
+    let exported = export_group(db, group_name)?;
+    atomic_write(&group_file_path(dir, group_name), |writer| writer.write_all(exported.as_bytes()))
+}
+
+fn group_file_path(dir: &Path, group_name: &str) -> PathBuf {
+    dir.join(format!("{}.pdbr", group_file_stem(group_name)))
+}
+
+/// Files that aren't `<group>.pdbr` are skipped, a group file that can't be read
+/// or parsed fails the whole load. The entries go in as they were saved, so
+/// their metadata from db.meta (times, ttl, compression, tombstones) still
+/// applies.
+pub fn load_from_dir(dir: impl AsRef<Path>) -> Result<Db, Error> {
+    let dir = dir.as_ref();
+    let mut db = load_from_file(dir.join(DIR_META_FILE))?;
+    for (group_name, path) in group_files(dir)? {
+        let exported = fs::read_to_string(&path).map_err(|why| Error::io(&path, why))?;
+        let in_file = |why: Error| Error::new(why.kind, format!("{}: {}", path.display(), why.message));
+        let group = parse_exported_group(&group_name, &exported).map_err(in_file)?;
+        if db.contains_key(&group_name) {
+            return Err(in_file(Error::new(
+                ErrorKind::Conflict,
+                format!("group '{}' already exists", group_name),
+            )));
+        }
+        db.insert(group_name, group);
+    }
+    Ok(db)
+}
+
+fn group_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, Error> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|why| Error::io(dir, why))? {
+        let path = entry.map_err(|why| Error::io(dir, why))?.path();
+        if path.extension().is_none_or(|extension| extension != "pdbr") {
+            continue;
+        }
+        let group_name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(group_name_from_stem);
+        if let Some(group_name) = group_name {
+            files.push((group_name, path));
+        }
+    }
+    Ok(files)
+}
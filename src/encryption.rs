@@ -0,0 +1,102 @@
+use crate::error::{Error, ErrorKind};
+use crate::format::{from_bytes_auto, to_plain_text};
+use crate::Db;
+
+const ENCRYPTED_MAGIC: &[u8; 4] = b"\xffPDE";
+const PASSPHRASE_MAGIC: &[u8; 4] = b"\xffPDP";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// ENCRYPTED_MAGIC, a random nonce, then the plain text sealed with
+/// ChaCha20-Poly1305.
+pub fn to_encrypted_bytes(db: &Db, key: &[u8; 32]) -> Vec<u8> {
+    let mut bytes = ENCRYPTED_MAGIC.to_vec();
+    bytes.extend_from_slice(&seal(db, key));
+    bytes
+}
+
+/// AuthenticationFailed when the key is wrong or the bytes were altered.
+pub fn from_encrypted_bytes(bytes: &[u8], key: &[u8; 32]) -> Result<Db, Error> {
+    let Some(sealed) = bytes.strip_prefix(ENCRYPTED_MAGIC) else {
+        return Err(Error::new(
+            ErrorKind::Corrupt,
+            "not an encrypted database".to_string(),
+        ));
+    };
+    open_sealed(sealed, key)
+}
+
+/// PASSPHRASE_MAGIC, the Argon2 salt, then the same layout as
+/// to_encrypted_bytes with the key derived from the passphrase.
+pub fn to_encrypted_bytes_with_passphrase(db: &Db, passphrase: &str) -> Result<Vec<u8>, Error> {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    use chacha20poly1305::aead::OsRng;
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let mut bytes = PASSPHRASE_MAGIC.to_vec();
+    bytes.extend_from_slice(&salt);
+    bytes.extend_from_slice(&seal(db, &key));
+    Ok(bytes)
+}
+
+/// AuthenticationFailed when the passphrase is wrong or the bytes were
+/// altered.
+pub fn from_encrypted_bytes_with_passphrase(bytes: &[u8], passphrase: &str) -> Result<Db, Error> {
+    let Some((salt, sealed)) = bytes
+        .strip_prefix(PASSPHRASE_MAGIC)
+        .and_then(|rest| rest.split_first_chunk::<SALT_LEN>())
+    else {
+        return Err(Error::new(
+            ErrorKind::Corrupt,
+            "not a passphrase encrypted database".to_string(),
+        ));
+    };
+    open_sealed(sealed, &derive_key(passphrase, salt)?)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], Error> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|why| Error::new(ErrorKind::Io, format!("could not derive key: {}", why)))?;
+    Ok(key)
+}
+
+fn seal(db: &Db, key: &[u8; 32]) -> Vec<u8> {
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    let cipher = ChaCha20Poly1305::new(key.into());
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    // Only fails for inputs larger than the cipher's limit of ~256 GiB.
+    let ciphertext = cipher
+        .encrypt(&nonce, to_plain_text(db).as_bytes())
+        .expect("database too large to encrypt");
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    sealed
+}
+
+fn open_sealed(sealed: &[u8], key: &[u8; 32]) -> Result<Db, Error> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+    if sealed.len() < NONCE_LEN {
+        return Err(Error::new(
+            ErrorKind::Corrupt,
+            "encrypted database is truncated".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let plain_text = ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::AuthenticationFailed,
+                "authentication failed, wrong key or tampered data".to_string(),
+            )
+        })?;
+    from_bytes_auto(&plain_text)
+}
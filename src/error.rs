@@ -0,0 +1,94 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// What went wrong, so callers can react without matching on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The database file does not exist, callers may want to create_db instead.
+    NotFound,
+    /// The file exists but could not be parsed.
+    Corrupt,
+    /// Reading or writing a file failed, source has the io::Error.
+    Io,
+    /// There is no group of that name, which includes the name group.
+    GroupNotFound,
+    /// The group exists but has nothing stored under the data_name.
+    DataNotFound,
+    /// The target of a write is already there and overwriting wasn't allowed.
+    Conflict,
+    /// A stored value could not be parsed as the requested type.
+    Parse,
+    /// A group or data name was refused by the DbConfig limits or a
+    /// NamespacedStore.
+    InvalidKey,
+    /// Data is longer than the DbConfig allows.
+    ValueTooLarge,
+    /// An arithmetic update like increment would overflow.
+    Overflow,
+    /// A tagged value was stored as a different type than the one requested.
+    TypeMismatch,
+    /// Written by a newer version of the format than this build understands.
+    UnsupportedVersion,
+    /// The stored checksum doesn't match the contents.
+    ChecksumMismatch,
+    /// A schema migration failed, the message says which one.
+    Migration,
+    /// Another process holds the database's lock file.
+    Locked,
+    /// Decryption failed, the key or passphrase is wrong or the data was altered.
+    AuthenticationFailed,
+    /// Data doesn't parse as the type a SchemaStore has for its group.
+    SchemaViolation,
+    /// A follower fell behind the events of its primary and missed some.
+    Lagged,
+    /// A try_ function found the handle locked and didn't wait for it.
+    WouldBlock,
+}
+
+/// What went wrong, the kind for matching on and a message for people.
+#[derive(Debug)]
+pub struct Error {
+    pub(crate) kind: ErrorKind,
+    pub(crate) message: String,
+    source: Option<io::Error>,
+}
+
+impl Error {
+    /// For write hooks and KvStore implementations outside this crate, their
+    /// errors have to be an Error too.
+    pub fn new(kind: ErrorKind, message: String) -> Error {
+        Error { kind, message, source: None }
+    }
+
+    pub(crate) fn io(path: &Path, why: io::Error) -> Error {
+        Error::new(ErrorKind::Io, format!("{}: {}", path.display(), why)).with_source(why)
+    }
+
+    pub(crate) fn with_source(mut self, source: io::Error) -> Error {
+        self.source = Some(source);
+        self
+    }
+
+    /// Compare with `why.kind() == ErrorKind::NotFound`.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The same text Display prints.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|why| why as &(dyn std::error::Error + 'static))
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::store::KvStore;
+
+/// A write made through a NotifyingStore.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbEvent {
+    GroupCreated {
+        group_name: String,
+    },
+    GroupRemoved {
+        group_name: String,
+    },
+    /// The data as written, so a follower can apply the event on its own.
+    DataWritten {
+        group_name: String,
+        data_name: String,
+        data: String,
+    },
+    DataRemoved {
+        group_name: String,
+        data_name: String,
+    },
+    /// This many events were dropped because the subscriber fell behind, it
+    /// comes before the first event delivered after them.
+    Missed(usize),
+}
+
+impl DbEvent {
+    fn group_name(&self) -> Option<&str> {
+        match self {
+            DbEvent::GroupCreated { group_name }
+            | DbEvent::GroupRemoved { group_name }
+            | DbEvent::DataWritten { group_name, .. }
+            | DbEvent::DataRemoved { group_name, .. } => Some(group_name),
+            DbEvent::Missed(_) => None,
+        }
+    }
+}
+
+// How many events a subscriber can fall behind by before they are dropped.
+const EVENT_BUFFER: usize = 1024;
+
+struct Subscriber {
+    // None for every group.
+    group_name: Option<String>,
+    events: SyncSender<DbEvent>,
+    // Shared with the Subscription, which only reads it once the store is
+    // gone and so can't send DbEvent::Missed itself.
+    missed: Arc<AtomicUsize>,
+}
+
+impl Subscriber {
+    // False once the receiving end is gone.
+    fn send(&mut self, event: &DbEvent) -> bool {
+        if let Some(group_name) = &self.group_name {
+            if event.group_name() != Some(group_name.as_str()) {
+                return true;
+            }
+        }
+        let missed = self.missed.load(Ordering::SeqCst);
+        if missed > 0 {
+            match self.events.try_send(DbEvent::Missed(missed)) {
+                Ok(()) => self.missed.store(0, Ordering::SeqCst),
+                Err(TrySendError::Full(_)) => {
+                    self.missed.store(missed + 1, Ordering::SeqCst);
+                    return true;
+                }
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+        match self.events.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.missed.fetch_add(1, Ordering::SeqCst);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// The receiving end of a NotifyingStore subscription, dropping it
+/// unsubscribes.
+pub struct Subscription {
+    events: Receiver<DbEvent>,
+    missed: Arc<AtomicUsize>,
+}
+
+impl Subscription {
+    /// The next event, waiting for one. None once the store was dropped and
+    /// every event was received.
+    pub fn recv(&self) -> Option<DbEvent> {
+        match self.events.recv() {
+            Ok(event) => Some(event),
+            Err(_) => self.missed_at_the_end(),
+        }
+    }
+
+    /// The next event when there already is one.
+    pub fn try_recv(&self) -> Option<DbEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => self.missed_at_the_end(),
+        }
+    }
+
+    /// recv until the store is dropped.
+    pub fn iter(&self) -> impl Iterator<Item = DbEvent> + '_ {
+        std::iter::from_fn(|| self.recv())
+    }
+
+    /// The events there already are.
+    pub fn try_iter(&self) -> impl Iterator<Item = DbEvent> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+
+    // A store dropped while the subscriber was behind never got to send
+    // DbEvent::Missed, it comes last instead.
+    fn missed_at_the_end(&self) -> Option<DbEvent> {
+        let missed = self.missed.swap(0, Ordering::SeqCst);
+        (missed > 0).then_some(DbEvent::Missed(missed))
+    }
+}
+
+/// Wraps a store and sends a DbEvent to every subscriber for each write made
+/// through it, after the write is made. Removes that found nothing and
+/// create_group for a group already there send none. Writers never
+/// wait for subscribers: one that falls behind by more than EVENT_BUFFER
+/// events misses the following ones and is told how many with
+/// DbEvent::Missed. Dropping a Subscription unsubscribes it.
+pub struct NotifyingStore<S> {
+    inner: S,
+    subscribers: Vec<Subscriber>,
+}
+
+impl<S: KvStore> NotifyingStore<S> {
+    pub fn new(inner: S) -> NotifyingStore<S> {
+        NotifyingStore {
+            inner,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Events for every group, from the next write on.
+    pub fn subscribe(&mut self) -> Subscription {
+        self.add_subscriber(None)
+    }
+
+    /// Only the events for group_name.
+    pub fn subscribe_group(&mut self, group_name: &str) -> Subscription {
+        self.add_subscriber(Some(group_name.to_string()))
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn add_subscriber(&mut self, group_name: Option<String>) -> Subscription {
+        let (events, receiver) = sync_channel(EVENT_BUFFER);
+        let missed = Arc::new(AtomicUsize::new(0));
+        self.subscribers.push(Subscriber {
+            group_name,
+            events,
+            missed: Arc::clone(&missed),
+        });
+        Subscription {
+            events: receiver,
+            missed,
+        }
+    }
+
+    fn notify(&mut self, event: DbEvent) {
+        self.subscribers
+            .retain_mut(|subscriber| subscriber.send(&event));
+    }
+}
+
+impl<S: KvStore> KvStore for NotifyingStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        let created = self.inner.create_group(group_name);
+        if created {
+            self.notify(DbEvent::GroupCreated {
+                group_name: group_name.to_string(),
+            });
+        }
+        created
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let previous = self.inner.put(group_name, data_name, data.clone())?;
+        self.notify(DbEvent::DataWritten {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+            data,
+        });
+        Ok(previous)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let removed = self.inner.remove_data_entry(group_name, data_name)?;
+        self.notify(DbEvent::DataRemoved {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+        });
+        Some(removed)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.inner.remove_group(group_name)?;
+        self.notify(DbEvent::GroupRemoved {
+            group_name: group_name.to_string(),
+        });
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::codec::{Codec, PlainTextCodec};
+use crate::error::{Error, ErrorKind};
+use crate::format::write_plain_text;
+use crate::lock::refuse_if_locked;
+use crate::Db;
+
+/// Overwrites the file in place, a crash halfway through leaves it truncated.
+/// Use atomic_save when the file must survive that. Locked while a FileGuard
+/// holds the path, save through the guard then.
+pub fn save_to_file(db: &Db, path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    refuse_if_locked(path)?;
+    let file = File::create(path).map_err(|why| Error::io(path, why))?;
+    let mut writer = BufWriter::new(file);
+    write_plain_text(db, &mut writer)
+        .and_then(|_| writer.flush())
+        .map_err(|why| Error::io(path, why))
+}
+
+/// Writes to a sibling .tmp file, syncs it and renames it over the destination,
+/// so the file on disk is always either the old or the new database.
+pub fn atomic_save(db: &Db, path: impl AsRef<Path>) -> Result<(), Error> {
+    refuse_if_locked(path.as_ref())?;
+    atomic_write(path.as_ref(), |writer| write_plain_text(db, writer))
+}
+
+pub(crate) fn atomic_write(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> Result<(), Error> {
+    let tmp_path = path.with_extension("tmp");
+    let file = File::create(&tmp_path).map_err(|why| Error::io(&tmp_path, why))?;
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)
+        .and_then(|_| writer.flush())
+        .and_then(|_| writer.get_ref().sync_all())
+        .map_err(|why| Error::io(&tmp_path, why))?;
+    drop(writer);
+    fs::rename(&tmp_path, path).map_err(|why| Error::io(path, why))?;
+    sync_parent_dir(path)
+}
+
+// The rename is only durable once the directory entry itself is on disk.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), Error> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)
+        .and_then(|dir_file| dir_file.sync_all())
+        .map_err(|why| Error::io(dir, why))
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Reads a database saved by any of the save functions. NotFound when the
+/// file doesn't exist, Corrupt and the format errors name the file.
+pub fn load_from_file(path: impl AsRef<Path>) -> Result<Db, Error> {
+    load_with_codec(path, &PlainTextCodec)
+}
+
+/// Saved the same way as atomic_save.
+pub fn save_with_codec(db: &Db, path: impl AsRef<Path>, codec: &dyn Codec) -> Result<(), Error> {
+    refuse_if_locked(path.as_ref())?;
+    let bytes = codec.encode(db)?;
+    atomic_write(path.as_ref(), |writer| writer.write_all(&bytes))
+}
+
+/// Reads a database saved by save_with_codec with the same codec, with the
+/// errors of load_from_file.
+pub fn load_with_codec(path: impl AsRef<Path>, codec: &dyn Codec) -> Result<Db, Error> {
+    let path = path.as_ref();
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{} does not exist", path.display()),
+            ))
+        }
+        Err(why) => return Err(Error::io(path, why)),
+    };
+    codec.decode(&bytes).map_err(|why| {
+        Error::new(why.kind, format!("{}: {}", path.display(), why.message))
+    })
+}
+
+/// `db.pdbr.1` is the newest backup, `db.pdbr.<keep>` the oldest.
+pub fn backup_path(path: &Path, generation: usize) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{}", generation));
+    PathBuf::from(backup)
+}
+
+pub(crate) fn remove_if_exists(path: &Path) -> Result<(), Error> {
+    match fs::remove_file(path) {
+        Err(why) if why.kind() != io::ErrorKind::NotFound => Err(Error::io(path, why)),
+        _ => Ok(()),
+    }
+}
+
+fn rename_if_exists(from: &Path, to: &Path) -> Result<(), Error> {
+    match fs::rename(from, to) {
+        Err(why) if why.kind() != io::ErrorKind::NotFound => Err(Error::io(from, why)),
+        _ => Ok(()),
+    }
+}
+
+/// Shifts the current file and its backups down one generation, dropping
+/// anything past keep, then writes db with atomic_save.
+pub fn save_with_backups(db: &Db, path: impl AsRef<Path>, keep: usize) -> Result<(), Error> {
+    let path = path.as_ref();
+    refuse_if_locked(path)?;
+    let mut generation = keep.max(1);
+    while backup_path(path, generation).exists() {
+        remove_if_exists(&backup_path(path, generation))?;
+        generation += 1;
+    }
+    if keep > 0 {
+        for generation in (1..keep).rev() {
+            rename_if_exists(&backup_path(path, generation), &backup_path(path, generation + 1))?;
+        }
+        rename_if_exists(path, &backup_path(path, 1))?;
+    }
+    atomic_save(db, path)
+}
+
+/// Tries the main file and then each backup from newest to oldest, returning
+/// the first that loads along with its generation, 0 being the main file.
+pub fn load_latest_valid(path: impl AsRef<Path>, keep: usize) -> Result<(Db, usize), Error> {
+    let path = path.as_ref();
+    let mut failures = Vec::new();
+    for generation in 0..=keep {
+        let candidate = match generation {
+            0 => path.to_path_buf(),
+            _ => backup_path(path, generation),
+        };
+        match load_from_file(&candidate) {
+            Ok(db) => return Ok((db, generation)),
+            Err(why) if why.kind == ErrorKind::NotFound => {}
+            Err(why) => failures.push(why.message),
+        }
+    }
+    if failures.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{} and its backups do not exist", path.display()),
+        ));
+    }
+    Err(Error::new(
+        ErrorKind::Corrupt,
+        format!("no valid generation of {}: {}", path.display(), failures.join("; ")),
+    ))
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::prelude::*;
+
+use crate::compression::{decompress, COMPRESSED_MAGIC};
+use crate::error::{Error, ErrorKind};
+use crate::Db;
+
+// First line of every file, followed by the format version.
+pub(crate) const FORMAT_MAGIC: &str = "%pdbr";
+/// The version written in the header of every saved file. Older versions
+/// still load, newer ones are UnsupportedVersion.
+pub const FORMAT_VERSION: u16 = 4;
+pub(crate) const CHECKSUM_PREFIX: &str = "%crc32 ";
+
+// Backslash escapes keep every entry on one line: \\ \n \r, and \, inside
+// group and data names since the first unescaped comma splits an entry. \%
+// only appears at the start of a line or of data, \0 stands for an empty
+// group or database name, which would otherwise be a blank line.
+pub(crate) fn escape_text(text: &str, escape_commas: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            ',' if escape_commas => escaped.push_str("\\,"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn unescape_text(text: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(',') => unescaped.push(','),
+            Some('%') => unescaped.push('%'),
+            Some('0') => {}
+            Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+            None => return Err("line ends with a lone '\\'".to_string()),
+        }
+    }
+    Ok(unescaped)
+}
+
+// From format 3 metadata lines start with %, so a name starting with one is
+// escaped.
+pub(crate) fn escape_db_name(db_name: &str) -> String {
+    escape_line(escape_text(db_name, false))
+}
+
+// From format 4 a leading % is escaped in groups and entries too: after the
+// name a line starting with % is a pool line, and data starting with one
+// refers to a pool line.
+fn escape_leading_percent(escaped: String) -> String {
+    if escaped.starts_with('%') {
+        format!("\\{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+// A whole line holding a group or database name, never blank.
+fn escape_line(escaped: String) -> String {
+    if escaped.is_empty() {
+        "\\0".to_string()
+    } else {
+        escape_leading_percent(escaped)
+    }
+}
+
+// Splits data_name,data at the first comma that isn't escaped.
+pub(crate) fn split_entry(line: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => return Some((&line[..i], &line[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The database as saved by save_to_file, groups and entries sorted so the
+/// same database gives the same text.
+pub fn to_plain_text(db: &Db) -> String {
+    let mut bytes = Vec::new();
+    // Writing into a Vec can't fail and everything written is a &str.
+    write_plain_text(db, &mut bytes).expect("writing into memory failed");
+    String::from_utf8(bytes).expect("database text is UTF-8")
+}
+
+/// Writes the database a line at a time, without building the whole text in
+/// memory first.
+pub fn write_to<W: Write>(db: &Db, writer: &mut W) -> Result<(), Error> {
+    write_plain_text(db, writer).map_err(|why| {
+        Error::new(ErrorKind::Io, format!("could not write database: {}", why)).with_source(why)
+    })
+}
+
+/// Reads anything from_bytes_auto accepts.
+pub fn read_from<R: Read>(reader: &mut R) -> Result<Db, Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|why| {
+        Error::new(ErrorKind::Io, format!("could not read database: {}", why)).with_source(why)
+    })?;
+    from_bytes_auto(&bytes)
+}
+
+pub(crate) fn write_plain_text<W: Write>(db: &Db, writer: &mut W) -> io::Result<()> {
+    write_pooled(db.get("name"), db, &[], &|_: &String| None, writer)
+}
+
+// Data that pool_ref gives an index into pool for is written as a reference
+// to that pool line instead of in full. groups may hold the name group, it is
+// skipped.
+pub(crate) fn write_pooled<W: Write, V: AsRef<str>>(
+    name_group: Option<&HashMap<String, String>>,
+    groups: &HashMap<String, HashMap<String, V>>,
+    pool: &[&str],
+    pool_ref: &dyn Fn(&V) -> Option<usize>,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut crc = !0u32;
+    let mut write_line = |line: &str| -> io::Result<()> {
+        crc = crc32_update(crc, line.as_bytes());
+        crc = crc32_update(crc, b"\n");
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\n")
+    };
+    write_line(&format!("{} {}", FORMAT_MAGIC, FORMAT_VERSION))?;
+    if let Some(name_group) = name_group {
+        let mut metadata: Vec<(&String, &String)> =
+            name_group.iter().filter(|(key, _)| *key != "name").collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            write_line(&format!("%{},{}", escape_text(key, true), escape_text(value, false)))?;
+        }
+    }
+    match name_group.and_then(|inner| inner.get("name")) {
+        Some(val) => write_line(&escape_db_name(val))?,
+        None => write_line("")?,
+    }
+    for (i, data) in pool.iter().enumerate() {
+        write_line(&format!("%{},{}", i, escape_text(data, false)))?;
+    }
+    // Sorted so the same database always gives the same bytes.
+    let mut group_names: Vec<&String> = groups.keys().filter(|key| *key != "name").collect();
+    group_names.sort();
+    for key in group_names {
+        write_line(&escape_line(escape_text(key, true)))?;
+        let mut entries: Vec<(&String, &V)> = groups[key].iter().collect();
+        entries.sort_by_key(|(data_name, _)| *data_name);
+        for (i, k) in entries {
+            let data = match pool_ref(k) {
+                Some(pool_index) => format!("%{}", pool_index),
+                None => escape_leading_percent(escape_text(k.as_ref(), false)),
+            };
+            write_line(&format!("{},{}", escape_leading_percent(escape_text(i, true)), data))?;
+        }
+    }
+    writeln!(writer, "{}{:08x}", CHECKSUM_PREFIX, !crc)
+}
+
+// 0 when the line isn't a %pdbr header, legacy files start with the name.
+fn format_version(first_line: Option<&str>) -> Result<u16, Error> {
+    let Some(line) = first_line.filter(|line| line.starts_with('%')) else {
+        return Ok(0);
+    };
+    let Some(version) = line
+        .strip_prefix(FORMAT_MAGIC)
+        .and_then(|version| version.strip_prefix(' '))
+        .and_then(|version| version.parse::<u16>().ok())
+    else {
+        return Err(Error::new(
+            ErrorKind::Corrupt,
+            "not a plain-text-db file".to_string(),
+        ));
+    };
+    if version > FORMAT_VERSION {
+        return Err(Error::new(
+            ErrorKind::UnsupportedVersion,
+            format!(
+                "file was written by a newer version (format {}, this build reads up to {})",
+                version, FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok(version)
+}
+
+// From format 2 the last line is "%crc32 <hex>" over every byte before it.
+// Hand edited files need the line updated, or the header set back to 1.
+fn strip_checksum(contents: &str) -> Result<&str, Error> {
+    let (body, stored) = split_checksum(contents);
+    let Some(stored) = stored.and_then(|crc| u32::from_str_radix(crc, 16).ok()) else {
+        return Err(Error::new(
+            ErrorKind::ChecksumMismatch,
+            "checksum line is missing, file is corrupt".to_string(),
+        ));
+    };
+    if crc32(body.as_bytes()) != stored {
+        return Err(Error::new(
+            ErrorKind::ChecksumMismatch,
+            "checksum mismatch, file is corrupt".to_string(),
+        ));
+    }
+    Ok(body)
+}
+
+// The contents before the checksum line and the hex on it, or all of the
+// contents when the last line isn't a checksum line.
+fn split_checksum(contents: &str) -> (&str, Option<&str>) {
+    let trimmed = contents.strip_suffix('\n').unwrap_or(contents);
+    let (body, last_line) = match trimmed.rfind('\n') {
+        Some(i) => (&trimmed[..i + 1], &trimmed[i + 1..]),
+        None => ("", trimmed),
+    };
+    match last_line.strip_prefix(CHECKSUM_PREFIX) {
+        Some(crc) => (body, Some(crc)),
+        None => (contents, None),
+    }
+}
+
+/// Parses a .pdbr file:
+///
+/// ```text
+/// %pdbr 4
+/// %schema,1
+/// Test
+/// temp_group
+/// temp,true
+///
+/// HashMap: [name:[name:test],temp_group:[temp:true]]
+/// ```
+///
+/// Files without the %pdbr header are format 0, from before escaping, and are
+/// read as is. A DedupDb writes data it holds more than once as a line like
+/// `%0,data` after the name, each entry then only has `%0` as its data.
+pub fn from_plain_text(contents: &str) -> Result<Db, Error> {
+    parse_plain_text(contents, &|_| true, None)
+}
+
+/// Only keeps the named groups, entries of every other group are skipped
+/// without being unescaped or copied. Names not in the file are ignored.
+pub fn from_plain_text_filtered(contents: &str, group_names: &[&str]) -> Result<Db, Error> {
+    parse_plain_text(contents, &|group_name| group_names.contains(&group_name), None)
+}
+
+/// Pulls one more group out of the same file into db, returning whether the
+/// file had it.
+pub fn load_additional_group(db: &mut Db, contents: &str, group_name: &str) -> Result<bool, Error> {
+    let mut loaded = from_plain_text_filtered(contents, &[group_name])?;
+    match loaded.remove(group_name) {
+        Some(group) => {
+            db.insert(group_name.to_string(), group);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Something from_plain_text_lossy dropped, line is 1 based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadWarning {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Loads what it can of a damaged file. Lines that can't be parsed are
+/// dropped, along with the entries of a group whose name can't be, and each
+/// drop is reported as a warning. A bad checksum is a warning too. Only a
+/// missing or unsupported header still fails.
+pub fn from_plain_text_lossy(contents: &str) -> Result<(Db, Vec<LoadWarning>), Error> {
+    let mut warnings = Vec::new();
+    let db = parse_plain_text(contents, &|_| true, Some(&mut warnings))?;
+    Ok((db, warnings))
+}
+
+// With warnings, line errors are pushed there and the line skipped instead of
+// failing the parse.
+fn parse_plain_text(
+    contents: &str,
+    keep_group: &dyn Fn(&str) -> bool,
+    mut warnings: Option<&mut Vec<LoadWarning>>,
+) -> Result<Db, Error> {
+    let corrupt = |line_no: usize, reason: String| {
+        Error::new(ErrorKind::Corrupt, format!("line {}: {}", line_no, reason))
+    };
+    macro_rules! skip_or_fail {
+        ($line_no:expr, $reason:expr) => {
+            match warnings.as_deref_mut() {
+                Some(warnings) => {
+                    warnings.push(LoadWarning { line: $line_no, message: $reason });
+                    continue;
+                }
+                None => return Err(corrupt($line_no, $reason)),
+            }
+        };
+    }
+    let version = format_version(contents.lines().next())?;
+    let body = if version < 2 {
+        contents
+    } else {
+        match (strip_checksum(contents), warnings.as_deref_mut()) {
+            (Ok(body), _) => body,
+            (Err(why), Some(warnings)) => {
+                let (body, _) = split_checksum(contents);
+                warnings.push(LoadWarning {
+                    line: contents.lines().count(),
+                    message: why.message,
+                });
+                body
+            }
+            (Err(why), None) => return Err(why),
+        }
+    };
+    let mut lines = body.lines().enumerate().map(|(i, line)| (i + 1, line)).peekable();
+    if version > 0 {
+        lines.next();
+    }
+    let unescape = |text: &str| {
+        if version == 0 {
+            Ok(text.to_string())
+        } else {
+            unescape_text(text)
+        }
+    };
+
+    let mut name_hash: HashMap<String, String> = HashMap::new();
+    while let Some((line_no, line)) = lines.next_if(|(_, line)| version >= 3 && line.starts_with('%')) {
+        let Some((key, value)) = split_entry(&line[1..]) else {
+            skip_or_fail!(line_no, format!("metadata line '{}' has no value", line));
+        };
+        let (key, value) = match (unescape(key), unescape(value)) {
+            (Ok(key), Ok(value)) => (key, value),
+            (Err(why), _) | (_, Err(why)) => skip_or_fail!(line_no, why),
+        };
+        if key == "name" {
+            skip_or_fail!(line_no, "'name' is reserved and cannot be metadata".to_string());
+        }
+        name_hash.insert(key, value);
+    }
+    // A blank name line is a database that was saved without a name, legacy
+    // files always have one.
+    let db_name = match lines.next() {
+        Some((line_no, name)) if !name.is_empty() => {
+            Some(unescape(name).map_err(|why| corrupt(line_no, why))?)
+        }
+        Some(_) if version > 0 => None,
+        Some((line_no, _)) => return Err(corrupt(line_no, "missing database name".to_string())),
+        None => return Err(corrupt(1, "missing database name".to_string())),
+    };
+    let mut db: Db = HashMap::new();
+    if let Some(db_name) = db_name {
+        name_hash.insert("name".to_string(), db_name);
+    }
+    if !name_hash.is_empty() {
+        db.insert("name".to_string(), name_hash);
+    }
+
+    let mut pool: HashMap<&str, String> = HashMap::new();
+    while let Some((line_no, line)) = lines.next_if(|(_, line)| version >= 4 && line.starts_with('%')) {
+        let Some((pool_index, data)) = line[1..].split_once(',') else {
+            skip_or_fail!(line_no, format!("pool line '{}' has no data", line));
+        };
+        match unescape(data) {
+            Ok(data) => pool.insert(pool_index, data),
+            Err(why) => skip_or_fail!(line_no, why),
+        };
+    }
+
+    let mut group_name: Option<String> = None;
+    let mut keeping = false;
+    for (line_no, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+        let entry = if version == 0 {
+            line.split_once(',')
+        } else {
+            split_entry(line)
+        };
+        if let Some((data_name, data)) = entry {
+            let Some(group) = &group_name else {
+                skip_or_fail!(line_no, format!("entry '{}' appears before any group", line));
+            };
+            if !keeping {
+                continue;
+            }
+            let group = group.clone();
+            let data = match data.strip_prefix('%').filter(|_| version >= 4) {
+                Some(pool_index) => match pool.get(pool_index) {
+                    Some(data) => Ok(data.clone()),
+                    None => Err(format!("data refers to pool line {}, which is missing", pool_index)),
+                },
+                None => unescape(data),
+            };
+            let (data_name, data) = match (unescape(data_name), data) {
+                (Ok(data_name), Ok(data)) => (data_name, data),
+                (Err(why), _) | (_, Err(why)) => {
+                    skip_or_fail!(line_no, format!("entry in group '{}': {}", group, why))
+                }
+            };
+            db.entry(group).or_default().insert(data_name, data);
+        } else {
+            // A group that is dropped takes its entries with it, they must
+            // not end up in the group before it.
+            group_name = Some(line.to_string());
+            keeping = false;
+            let group = match unescape(line) {
+                Ok(group) => group,
+                Err(why) => skip_or_fail!(line_no, format!("{}, its entries were dropped", why)),
+            };
+            if group == "name" {
+                skip_or_fail!(line_no, "'name' is reserved and cannot be a group".to_string());
+            }
+            keeping = keep_group(&group);
+            if keeping {
+                db.entry(group.clone()).or_default();
+            }
+            group_name = Some(group);
+        }
+    }
+    Ok(db)
+}
+
+/// Accepts both plain .pdbr text and the output of to_compressed_bytes.
+pub fn from_bytes_auto(bytes: &[u8]) -> Result<Db, Error> {
+    let corrupt = |message: String| Error::new(ErrorKind::Corrupt, message);
+    let plain_text = match bytes.strip_prefix(COMPRESSED_MAGIC) {
+        Some(compressed) => decompress(compressed)?,
+        None => String::from_utf8(bytes.to_vec())
+            .map_err(|_| corrupt("not a plain-text-db file".to_string()))?,
+    };
+    from_plain_text(&plain_text)
+}
+
+// Bitwise CRC-32 (IEEE), fast enough for log records.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !crc32_update(!0u32, bytes)
+}
+
+// Feeds more bytes into a running CRC, start from !0 and invert the result.
+pub(crate) fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::db::{
+    contains_group, insert_data, list_data_names, read_as, read_optional, remove_data_entry,
+    store_optional, take_data,
+};
+use crate::error::Error;
+use crate::Db;
+
+/// One group of a database, so the group name isn't repeated on every call.
+/// The group isn't checked until it is used, calls on a group that doesn't
+/// exist are GroupNotFound like the free functions.
+pub struct GroupHandle<'a> {
+    db: &'a mut Db,
+    group_name: String,
+}
+
+pub fn group_handle<'a>(db: &'a mut Db, group_name: &str) -> GroupHandle<'a> {
+    GroupHandle {
+        db,
+        group_name: group_name.to_string(),
+    }
+}
+
+impl GroupHandle<'_> {
+    pub fn name(&self) -> &str {
+        &self.group_name
+    }
+
+    pub fn exists(&self) -> bool {
+        contains_group(self.db, &self.group_name)
+    }
+
+    pub fn get<T: FromStr>(&self, data_name: &str) -> Result<T, Error>
+    where
+        T::Err: fmt::Display,
+    {
+        read_as(self.db, &self.group_name, data_name)
+    }
+
+    /// Returns what was stored under data_name before.
+    pub fn insert<T: fmt::Display>(
+        &mut self,
+        data_name: &str,
+        data: &T,
+    ) -> Result<Option<String>, Error> {
+        insert_data(
+            self.db,
+            &self.group_name,
+            data_name,
+            data.to_string(),
+            false,
+        )
+    }
+
+    pub fn remove(&mut self, data_name: &str) -> Option<String> {
+        remove_data_entry(self.db, &self.group_name, data_name)
+    }
+
+    /// Sorted.
+    pub fn data_names(&self) -> Result<Vec<String>, Error> {
+        list_data_names(self.db, &self.group_name)
+    }
+}
+
+/// A GroupHandle whose entries are all read and written as T, so they can't
+/// be read back as another type by mistake.
+pub struct TypedGroup<'a, T> {
+    handle: GroupHandle<'a>,
+    value_type: PhantomData<T>,
+}
+
+pub fn typed_group<'a, T>(db: &'a mut Db, group_name: &str) -> TypedGroup<'a, T> {
+    TypedGroup {
+        handle: group_handle(db, group_name),
+        value_type: PhantomData,
+    }
+}
+
+impl<T> TypedGroup<'_, T>
+where
+    T: FromStr + fmt::Display,
+    T::Err: fmt::Display,
+{
+    pub fn name(&self) -> &str {
+        self.handle.name()
+    }
+
+    pub fn exists(&self) -> bool {
+        self.handle.exists()
+    }
+
+    pub fn get(&self, data_name: &str) -> Result<T, Error> {
+        self.handle.get(data_name)
+    }
+
+    /// Returns what was stored under data_name before, data that doesn't
+    /// parse as T is an error and isn't replaced.
+    pub fn insert(&mut self, data_name: &str, data: &T) -> Result<Option<T>, Error> {
+        let previous = read_optional(self.handle.db, &self.handle.group_name, data_name)?;
+        self.handle.insert(data_name, data)?;
+        Ok(previous)
+    }
+
+    /// Data that doesn't parse as T is an error and isn't removed.
+    pub fn remove(&mut self, data_name: &str) -> Result<T, Error> {
+        take_data(self.handle.db, &self.handle.group_name, data_name)
+    }
+
+    pub fn data_names(&self) -> Result<Vec<String>, Error> {
+        self.handle.data_names()
+    }
+}
+
+/// One entry parsed as T, changed in memory and written back by commit.
+/// Dropping it without commit leaves db as it was.
+pub struct DataEntry<'a, T> {
+    db: &'a mut Db,
+    group_name: String,
+    data_name: String,
+    value: Option<T>,
+}
+
+/// Fails straight away when the group is missing or the entry doesn't parse
+/// as T, a missing entry starts out as None.
+pub fn data_entry<'a, T>(
+    db: &'a mut Db,
+    group_name: &str,
+    data_name: &str,
+) -> Result<DataEntry<'a, T>, Error>
+where
+    T: FromStr + fmt::Display,
+    T::Err: fmt::Display,
+{
+    let value = read_optional(db, group_name, data_name)?;
+    Ok(DataEntry {
+        db,
+        group_name: group_name.to_string(),
+        data_name: data_name.to_string(),
+        value,
+    })
+}
+
+impl<T> DataEntry<'_, T>
+where
+    T: FromStr + fmt::Display,
+    T::Err: fmt::Display,
+{
+    /// None while the entry has no value, stored or given.
+    pub fn get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Gives the entry default when it has no value. Like everything else it
+    /// is only written by commit.
+    pub fn or_insert(self, default: T) -> Self {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(mut self, f: impl FnOnce() -> T) -> Self {
+        if self.value.is_none() {
+            self.value = Some(f());
+        }
+        self
+    }
+
+    /// Only called when there is a value.
+    pub fn and_modify(mut self, f: impl FnOnce(&mut T)) -> Self {
+        if let Some(value) = &mut self.value {
+            f(value);
+        }
+        self
+    }
+
+    /// Stores the value, or removes the entry when there is none, and returns
+    /// it.
+    pub fn commit(self) -> Result<Option<T>, Error> {
+        store_optional(
+            self.db,
+            &self.group_name,
+            &self.data_name,
+            self.value.as_ref(),
+        )?;
+        Ok(self.value)
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::db::{get_data, insert_data, parse_error};
+use crate::error::{Error, ErrorKind};
+use crate::timestamps::{entry_meta_value, now, set_entry_meta_value};
+use crate::Db;
+
+// Kept in the name group with the other metadata, so history is saved with
+// the database and groups without it are saved exactly as before.
+const KEEP_PREFIX: &str = "history_keep:";
+
+pub(crate) fn keep_key(group_name: &str) -> String {
+    format!("{}{}", KEEP_PREFIX, group_name)
+}
+
+pub(crate) fn is_keep_key(key: &str) -> bool {
+    key.starts_with(KEEP_PREFIX)
+}
+
+/// Makes insert_data, and every write built on it, keep up to keep earlier
+/// values of each entry in the group. 0 turns it off, history already kept
+/// stays. The setting moves with rename_group and goes with remove_group.
+pub fn enable_history(db: &mut Db, group_name: &str, keep: usize) {
+    let name_group = db.entry("name".to_string()).or_default();
+    if keep == 0 {
+        name_group.remove(&keep_key(group_name));
+    } else {
+        name_group.insert(keep_key(group_name), keep.to_string());
+    }
+}
+
+fn history_keep(db: &Db, group_name: &str) -> usize {
+    db.get("name")
+        .and_then(|name_group| name_group.get(&keep_key(group_name)))
+        .and_then(|keep| keep.parse().ok())
+        .unwrap_or(0)
+}
+
+// Oldest first, as (seconds since the Unix epoch, data).
+fn load_history(db: &Db, group_name: &str, data_name: &str) -> Result<Vec<(u64, String)>, Error> {
+    let Some(history) = entry_meta_value(db, "history", group_name, data_name) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_str(history).map_err(|why| {
+        Error::new(
+            ErrorKind::Corrupt,
+            format!(
+                "history of '{}' in group '{}' is invalid: {}",
+                data_name, group_name, why
+            ),
+        )
+    })
+}
+
+fn store_history(db: &mut Db, group_name: &str, data_name: &str, history: &[(u64, String)]) {
+    let history = (!history.is_empty()).then(|| serde_json::json!(history).to_string());
+    set_entry_meta_value(db, "history", group_name, data_name, history);
+}
+
+// What insert_data is about to replace, read before it does. None when the
+// group keeps no history or there is nothing readable to replace.
+pub(crate) struct Replaced {
+    keep: usize,
+    history: Vec<(u64, String)>,
+    data: String,
+}
+
+pub(crate) fn history_before_write(db: &Db, group_name: &str, data_name: &str) -> Result<Option<Replaced>, Error> {
+    let keep = history_keep(db, group_name);
+    if keep == 0 {
+        return Ok(None);
+    }
+    // As get_data returns it, so compressed data is kept decompressed.
+    let Ok(data) = get_data(db, group_name, data_name) else {
+        return Ok(None);
+    };
+    let data = data.into_owned();
+    let history = load_history(db, group_name, data_name)?;
+    Ok(Some(Replaced { keep, history, data }))
+}
+
+// Adds the replaced data to the history once the write is made, the oldest
+// values go once there are more than keep.
+pub(crate) fn history_after_write(db: &mut Db, group_name: &str, data_name: &str, replaced: Replaced) {
+    let Replaced { keep, mut history, data } = replaced;
+    history.push((now(), data));
+    let excess = history.len().saturating_sub(keep);
+    history.drain(..excess);
+    store_history(db, group_name, data_name, &history);
+}
+
+/// The same as insert_data, which keeps history itself now. Kept for callers
+/// written before it did.
+pub fn insert_data_with_history(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    data: String,
+    create_group: bool,
+) -> Result<Option<String>, Error> {
+    insert_data(db, group_name, data_name, data, create_group)
+}
+
+/// The entry's earlier values parsed as T with when they were replaced,
+/// oldest first.
+pub fn read_history<T: FromStr>(
+    db: &Db,
+    group_name: &str,
+    data_name: &str,
+) -> Result<Vec<(u64, T)>, Error>
+where
+    T::Err: fmt::Display,
+{
+    get_data(db, group_name, data_name)?;
+    load_history(db, group_name, data_name)?
+        .into_iter()
+        .map(|(replaced, data)| {
+            let value = data
+                .parse::<T>()
+                .map_err(|why| parse_error(group_name, data_name, &data, why))?;
+            Ok((replaced, value))
+        })
+        .collect()
+}
+
+/// Puts back the value the entry had steps writes ago, dropping it and the
+/// newer values from the history. Returns the data it replaced.
+pub fn revert(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    steps: usize,
+) -> Result<String, Error> {
+    get_data(db, group_name, data_name)?;
+    let mut history = load_history(db, group_name, data_name)?;
+    if steps == 0 || steps > history.len() {
+        return Err(Error::new(
+            ErrorKind::DataNotFound,
+            format!(
+                "'{}' in group '{}' has {} earlier values, can't go back {}",
+                data_name,
+                group_name,
+                history.len(),
+                steps
+            ),
+        ));
+    }
+    let (_, data) = history.remove(history.len() - steps);
+    history.truncate(history.len() + 1 - steps);
+    let replaced =
+        insert_data(db, group_name, data_name, data, false)?.expect("the entry was just found");
+    store_history(db, group_name, data_name, &history);
+    Ok(replaced)
+}
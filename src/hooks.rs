@@ -0,0 +1,140 @@
+use crate::error::Error;
+use crate::store::KvStore;
+use crate::wal::WalOp;
+use crate::Db;
+
+/// Called with every op before WriteHooks::apply makes it. Returning an error
+/// refuses the write.
+pub type WriteHook = Box<dyn Fn(&WalOp) -> Result<(), Error> + Send + Sync>;
+
+/// Checks and audit logging run in front of writes. Hooks only see the op,
+/// not the database, so they can't change it behind the write's back or wait
+/// on a lock the writer holds. They run on the writer's thread and should
+/// return quickly. See HookedStore for running them on every write to a
+/// store.
+#[derive(Default)]
+pub struct WriteHooks {
+    hooks: Vec<WriteHook>,
+}
+
+impl WriteHooks {
+    pub fn new() -> WriteHooks {
+        WriteHooks::default()
+    }
+
+    /// Hooks run in the order they were added.
+    pub fn add(&mut self, hook: impl Fn(&WalOp) -> Result<(), Error> + Send + Sync + 'static) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Runs every hook and then applies op to db. The first hook to fail stops
+    /// the write, db is left unchanged and the hook's error is returned, as is
+    /// the error of an op WalOp::apply refuses.
+    pub fn apply(&self, db: &mut Db, op: &WalOp) -> Result<(), Error> {
+        self.check(op)?;
+        op.apply(db)
+    }
+
+    fn check(&self, op: &WalOp) -> Result<(), Error> {
+        for hook in &self.hooks {
+            hook(op)?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a store and runs the hooks before every write made through it. A
+/// refused put returns the hook's error. create_group and the removes can't
+/// return one, a refused create_group is false and a refused remove is None
+/// like one that found nothing, take_refusal says which it was.
+pub struct HookedStore<S> {
+    inner: S,
+    hooks: WriteHooks,
+    refusal: Option<Error>,
+}
+
+impl<S: KvStore> HookedStore<S> {
+    pub fn new(inner: S, hooks: WriteHooks) -> HookedStore<S> {
+        HookedStore {
+            inner,
+            hooks,
+            refusal: None,
+        }
+    }
+
+    pub fn add_hook(&mut self, hook: impl Fn(&WalOp) -> Result<(), Error> + Send + Sync + 'static) {
+        self.hooks.add(hook);
+    }
+
+    /// The error a hook refused the last create_group or remove with, None
+    /// when it wasn't refused.
+    pub fn take_refusal(&mut self) -> Option<Error> {
+        self.refusal.take()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    // Remembers the refusal for take_refusal, true when the write may go on.
+    fn allowed(&mut self, op: WalOp) -> bool {
+        self.refusal = self.hooks.check(&op).err();
+        self.refusal.is_none()
+    }
+}
+
+impl<S: KvStore> KvStore for HookedStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        let op = WalOp::CreateGroup {
+            group_name: group_name.to_string(),
+        };
+        self.allowed(op) && self.inner.create_group(group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        self.hooks.check(&WalOp::Append {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+            data: data.clone(),
+        })?;
+        self.inner.put(group_name, data_name, data)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let op = WalOp::RemoveEntry {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+        };
+        if !self.allowed(op) {
+            return None;
+        }
+        self.inner.remove_data_entry(group_name, data_name)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let op = WalOp::RemoveGroup {
+            group_name: group_name.to_string(),
+        };
+        if !self.allowed(op) {
+            return None;
+        }
+        self.inner.remove_group(group_name)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
@@ -0,0 +1,203 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::db::{get_data, get_group, insert_data, list_groups, remove_data_entry, remove_group};
+use crate::error::{Error, ErrorKind};
+use crate::json::json_scalar_to_string;
+use crate::persist::PersistentDb;
+
+// Bodies are JSON scalars, anything bigger than this is refused.
+const MAX_BODY_LEN: usize = 16 * 1024 * 1024;
+
+/// Serves db over HTTP on listener until accepting a connection fails. Bind
+/// the listener yourself, to port 0 for an ephemeral one. Connections are
+/// handled one at a time, one request each.
+///
+/// ```text
+/// GET    /groups                    group names
+/// GET    /groups/{group}            the group as an object
+/// GET    /groups/{group}/{data}     the data as a JSON string
+/// PUT    /groups/{group}/{data}     stores the JSON scalar body
+/// DELETE /groups/{group}[/{data}]   removes the group or entry
+/// POST   /save                      saves db to its file
+/// ```
+///
+/// Errors answer with {"error": kind, "message": ..}, 404 for the not found
+/// kinds.
+pub fn serve_http(db: &mut PersistentDb, listener: TcpListener) -> Result<(), Error> {
+    loop {
+        let (stream, _) = listener.accept().map_err(|why| {
+            Error::new(
+                ErrorKind::Io,
+                format!("accepting a connection failed: {}", why),
+            )
+            .with_source(why)
+        })?;
+        // A client that goes away only loses its own response.
+        let _ = handle_connection(db, stream);
+    }
+}
+
+fn handle_connection(db: &mut PersistentDb, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let (status, body) = match read_request(&mut reader)? {
+        Some((method, path, body)) => route(db, &method, &path, &body),
+        None => error_response(400, "BadRequest", "malformed request"),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// The method, path and body, None when the request can't be understood.
+fn read_request(reader: &mut impl BufRead) -> std::io::Result<Option<(String, String, String)>> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let mut content_len = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                match value.trim().parse::<usize>() {
+                    Ok(len) if len <= MAX_BODY_LEN => content_len = len,
+                    _ => return Ok(None),
+                }
+            }
+        }
+    }
+    let mut body = vec![0; content_len];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(None);
+    };
+    Ok(Some((method.to_string(), path.to_string(), body)))
+}
+
+fn route(db: &mut PersistentDb, method: &str, path: &str, body: &str) -> (u16, String) {
+    let path = path.split('?').next().unwrap_or_default();
+    let Some(segments) = path
+        .trim_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect::<Option<Vec<String>>>()
+    else {
+        return error_response(400, "BadRequest", "path is not valid percent encoded UTF-8");
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let result = match (method, segments.as_slice()) {
+        ("GET", ["groups"]) => Ok(serde_json::json!(list_groups(db)).to_string()),
+        ("GET", ["groups", group_name]) => {
+            get_group(db, group_name).map(|group| serde_json::json!(group).to_string())
+        }
+        ("GET", ["groups", group_name, data_name]) => {
+            get_data(db, group_name, data_name).map(|data| serde_json::json!(data).to_string())
+        }
+        ("PUT", ["groups", group_name, data_name]) => put_data(db, group_name, data_name, body),
+        // Looked up first so a miss has the same error as a GET.
+        ("DELETE", ["groups", group_name]) => get_group(db, group_name)
+            .map(|_| ())
+            .map(|()| serde_json::json!({ "removed": remove_group(db, group_name) }).to_string()),
+        ("DELETE", ["groups", group_name, data_name]) => get_data(db, group_name, data_name)
+            .map(|_| ())
+            .map(|()| serde_json::json!(remove_data_entry(db, group_name, data_name)).to_string()),
+        ("POST", ["save"]) => db.save().map(|()| "null".to_string()),
+        (_, ["groups"] | ["groups", _] | ["groups", _, _] | ["save"]) => {
+            return error_response(
+                405,
+                "MethodNotAllowed",
+                &format!("{} is not allowed on {}", method, path),
+            );
+        }
+        _ => return error_response(404, "NotFound", &format!("no route for {}", path)),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err(why) => {
+            let status = match why.kind() {
+                ErrorKind::NotFound | ErrorKind::GroupNotFound | ErrorKind::DataNotFound => 404,
+                ErrorKind::InvalidKey | ErrorKind::ValueTooLarge | ErrorKind::Parse => 400,
+                ErrorKind::Conflict => 409,
+                _ => 500,
+            };
+            error_response(status, &format!("{:?}", why.kind()), why.message())
+        }
+    }
+}
+
+fn put_data(
+    db: &mut PersistentDb,
+    group_name: &str,
+    data_name: &str,
+    body: &str,
+) -> Result<String, Error> {
+    let data = serde_json::from_str(body)
+        .ok()
+        .and_then(json_scalar_to_string)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::Parse,
+                format!(
+                    "body for '{}' in group '{}' is not a JSON string, number or bool",
+                    data_name, group_name
+                ),
+            )
+        })?;
+    let replaced = insert_data(db, group_name, data_name, data, true)?;
+    Ok(serde_json::json!({ "replaced": replaced }).to_string())
+}
+
+fn error_response(status: u16, kind: &str, message: &str) -> (u16, String) {
+    (
+        status,
+        serde_json::json!({ "error": kind, "message": message }).to_string(),
+    )
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+// %XX escapes decoded, None when they aren't valid UTF-8.
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
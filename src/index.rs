@@ -0,0 +1,166 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::store::KvStore;
+
+/// Wraps a store and keeps secondary indexes over some of its groups, from a
+/// key extracted out of each entry to the data_names that have it. Writes
+/// through the wrapper keep the indexes current. Indexes only live in
+/// memory, create them again after loading.
+pub struct IndexedStore<S> {
+    inner: S,
+    indexes: HashMap<String, Index>,
+}
+
+// The key of some data, None for data that doesn't parse and is left out of
+// the index.
+type Extract = Box<dyn Fn(&str) -> Option<String>>;
+
+struct Index {
+    group_name: String,
+    extract: Extract,
+    by_key: HashMap<String, BTreeSet<String>>,
+    key_of: HashMap<String, String>,
+}
+
+impl Index {
+    fn insert(&mut self, data_name: &str, data: &str) {
+        self.remove(data_name);
+        if let Some(key) = (self.extract)(data) {
+            self.by_key
+                .entry(key.clone())
+                .or_default()
+                .insert(data_name.to_string());
+            self.key_of.insert(data_name.to_string(), key);
+        }
+    }
+
+    fn remove(&mut self, data_name: &str) {
+        let Some(key) = self.key_of.remove(data_name) else {
+            return;
+        };
+        if let Some(data_names) = self.by_key.get_mut(&key) {
+            data_names.remove(data_name);
+            if data_names.is_empty() {
+                self.by_key.remove(&key);
+            }
+        }
+    }
+}
+
+impl<S: KvStore> IndexedStore<S> {
+    pub fn new(inner: S) -> IndexedStore<S> {
+        IndexedStore {
+            inner,
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// Indexes the group's entries by extract applied to their data parsed as
+    /// T, replacing any index already called index_name. Entries that don't
+    /// parse as T aren't indexed. The group doesn't have to exist yet.
+    pub fn create_index<T, K, F>(&mut self, group_name: &str, index_name: &str, extract: F)
+    where
+        T: FromStr + 'static,
+        K: fmt::Display,
+        F: Fn(&T) -> K + 'static,
+    {
+        let mut index = Index {
+            group_name: group_name.to_string(),
+            extract: Box::new(move |data| {
+                data.parse::<T>()
+                    .ok()
+                    .map(|value| extract(&value).to_string())
+            }),
+            by_key: HashMap::new(),
+            key_of: HashMap::new(),
+        };
+        for data_name in self.inner.list_data_names(group_name).unwrap_or_default() {
+            if let Ok(data) = self.inner.get_raw(group_name, &data_name) {
+                index.insert(&data_name, &data);
+            }
+        }
+        self.indexes.insert(index_name.to_string(), index);
+    }
+
+    /// False when there was no index called index_name.
+    pub fn drop_index(&mut self, index_name: &str) -> bool {
+        self.indexes.remove(index_name).is_some()
+    }
+
+    /// The data_names whose key is key, sorted, or None when there is no
+    /// index called index_name.
+    pub fn lookup_by_index<K: fmt::Display>(
+        &self,
+        index_name: &str,
+        key: &K,
+    ) -> Option<Vec<String>> {
+        let index = self.indexes.get(index_name)?;
+        let data_names = index.by_key.get(&key.to_string());
+        Some(
+            data_names
+                .map(|data_names| data_names.iter().cloned().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn indexes_of<'a>(&'a mut self, group_name: &'a str) -> impl Iterator<Item = &'a mut Index> {
+        self.indexes
+            .values_mut()
+            .filter(move |index| index.group_name == group_name)
+    }
+}
+
+impl<S: KvStore> KvStore for IndexedStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        self.inner.create_group(group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let replaced = self.inner.put(group_name, data_name, data.clone())?;
+        for index in self.indexes_of(group_name) {
+            index.insert(data_name, &data);
+        }
+        Ok(replaced)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let removed = self.inner.remove_data_entry(group_name, data_name)?;
+        for index in self.indexes_of(group_name) {
+            index.remove(data_name);
+        }
+        Some(removed)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.inner.remove_group(group_name)?;
+        for index in self.indexes_of(group_name) {
+            index.by_key.clear();
+            index.key_of.clear();
+        }
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
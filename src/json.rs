@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use crate::db::{contains_data, get_data, insert_data};
+use crate::error::{Error, ErrorKind};
+use crate::Db;
+
+/// Same layout as the HashMap, including the name group, with keys sorted so
+/// snapshots can be diffed.
+/// {"name": {"name": "test"}, "temp_group": {"temp": "true"}}
+pub fn to_json_string(db: &Db) -> String {
+    let mut json_db = serde_json::Map::new();
+    for (group_name, group) in db {
+        let json_group: serde_json::Map<String, serde_json::Value> = group
+            .iter()
+            .map(|(data_name, data)| (data_name.clone(), serde_json::Value::String(data.clone())))
+            .collect();
+        json_db.insert(group_name.clone(), serde_json::Value::Object(json_group));
+    }
+    format!("{:#}", serde_json::Value::Object(json_db))
+}
+
+/// Numbers and bools are accepted as values so hand edited files don't need
+/// everything quoted, they are stored as their text.
+pub fn from_json_str(contents: &str) -> Result<Db, Error> {
+    let corrupt = |message: String| Error::new(ErrorKind::Corrupt, message);
+    let json_db: serde_json::Value =
+        serde_json::from_str(contents).map_err(|why| corrupt(format!("invalid JSON: {}", why)))?;
+    let serde_json::Value::Object(json_db) = json_db else {
+        return Err(corrupt("expected a JSON object of groups".to_string()));
+    };
+    let mut db: Db = HashMap::new();
+    for (group_name, json_group) in json_db {
+        let serde_json::Value::Object(json_group) = json_group else {
+            return Err(corrupt(format!("group '{}' is not a JSON object", group_name)));
+        };
+        let mut group: HashMap<String, String> = HashMap::new();
+        for (data_name, data) in json_group {
+            let Some(data) = json_scalar_to_string(data) else {
+                return Err(corrupt(format!(
+                    "value of '{}' in group '{}' is not a string, number or bool",
+                    data_name, group_name
+                )));
+            };
+            group.insert(data_name, data);
+        }
+        db.insert(group_name, group);
+    }
+    Ok(db)
+}
+
+// Strings as is, numbers and bools as their text.
+pub(crate) fn json_scalar_to_string(value: serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(value) => Some(value),
+        serde_json::Value::Number(value) => Some(value.to_string()),
+        serde_json::Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// One {"group": .., "data_name": .., "data": ..} object per line, sorted by
+/// group then data_name so exports can be diffed. Empty groups have no lines.
+pub fn to_ndjson(db: &Db) -> String {
+    let mut group_names: Vec<&String> = db.keys().filter(|key| *key != "name").collect();
+    group_names.sort();
+    let mut ndjson = String::new();
+    for group_name in group_names {
+        let mut entries: Vec<(&String, &String)> = db[group_name].iter().collect();
+        entries.sort();
+        for (data_name, data) in entries {
+            let record = serde_json::json!({
+                "group": group_name,
+                "data_name": data_name,
+                "data": data,
+            });
+            ndjson.push_str(&record.to_string());
+            ndjson.push('\n');
+        }
+    }
+    ndjson
+}
+
+/// Every line is checked before any is inserted, so a malformed record leaves
+/// db unchanged. Existing entries are only replaced when overwrite is set,
+/// expired ones count as missing. Entries are written like insert_data does,
+/// times and all. Returns how many entries were written.
+pub fn import_ndjson(db: &mut Db, ndjson: &str, overwrite: bool) -> Result<usize, Error> {
+    let mut records = Vec::new();
+    for (i, line) in ndjson.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let corrupt = |reason: String| {
+            Error::new(ErrorKind::Corrupt, format!("line {}: {}", i + 1, reason))
+        };
+        let record: serde_json::Value = serde_json::from_str(line)
+            .map_err(|why| corrupt(format!("invalid JSON: {}", why)))?;
+        let serde_json::Value::Object(mut record) = record else {
+            return Err(corrupt("expected a JSON object".to_string()));
+        };
+        let mut field = |key: &str| {
+            record
+                .remove(key)
+                .and_then(json_scalar_to_string)
+                .ok_or_else(|| corrupt(format!("'{}' is missing or not a string, number or bool", key)))
+        };
+        let (group_name, data_name, data) = (field("group")?, field("data_name")?, field("data")?);
+        if group_name == "name" {
+            return Err(corrupt("'name' is reserved and cannot be a group".to_string()));
+        }
+        records.push((group_name, data_name, data));
+    }
+    let mut written = 0;
+    for (group_name, data_name, data) in records {
+        if overwrite || !contains_data(db, &group_name, &data_name) {
+            insert_data(db, &group_name, &data_name, data, true)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// The entry as whatever JSON it holds, for tools that don't know the type.
+/// Data that isn't JSON is a string, or a Parse error when strict is set.
+/// Text that happens to be JSON, like "5" or "true", comes back as the
+/// number or bool.
+pub fn read_value(
+    db: &Db,
+    group_name: &str,
+    data_name: &str,
+    strict: bool,
+) -> Result<serde_json::Value, Error> {
+    let data = get_data(db, group_name, data_name)?;
+    match serde_json::from_str(&data) {
+        Ok(value) => Ok(value),
+        Err(why) if strict => Err(Error::new(
+            ErrorKind::Parse,
+            format!("'{}' in group '{}' is not JSON: {}", data_name, group_name, why),
+        )),
+        Err(_) => Ok(serde_json::Value::String(data.into_owned())),
+    }
+}
+
+/// Stores value so read_value gives it back. Strings are stored as is unless
+/// they would read back as JSON, everything else as JSON text. Returns what
+/// it replaced, like insert_data.
+pub fn insert_value(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    value: &serde_json::Value,
+    create_group: bool,
+) -> Result<Option<String>, Error> {
+    let reads_as_json = |text: &str| serde_json::from_str::<serde_json::Value>(text).is_ok();
+    let data = match value {
+        serde_json::Value::String(text) if !reads_as_json(text) => text.clone(),
+        value => value.to_string(),
+    };
+    insert_data(db, group_name, data_name, data, create_group)
+}
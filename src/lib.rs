@@ -0,0 +1,131 @@
+//! A small database kept in a plain text `.pdbr` file.
+//!
+//! A database is a map of groups, each a map of data_name to data. The
+//! special `name` group holds the database name and its metadata.
+
+use std::collections::HashMap;
+
+mod audit;
+mod autosave;
+#[cfg(any(feature = "compression", feature = "tcp"))]
+mod base64;
+mod cache;
+mod codec;
+mod compression;
+mod config;
+mod csv;
+mod db;
+mod dedup;
+mod diff;
+mod dir;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod error;
+mod events;
+mod file;
+mod format;
+mod handle;
+mod history;
+mod hooks;
+#[cfg(feature = "http")]
+mod http;
+mod index;
+mod json;
+mod list;
+mod lock;
+mod lru;
+mod manager;
+mod meta;
+mod migration;
+mod namespace;
+mod persist;
+mod replication;
+mod schema;
+mod search;
+mod stats;
+mod store;
+#[cfg(feature = "tcp")]
+mod tcp;
+mod timestamps;
+mod tombstone;
+mod txn;
+mod undo;
+mod wal;
+
+/// `HashMap: [name:[name:db_name],group_name:[data_name:data]]`
+pub type Db = HashMap<String, HashMap<String, String>>;
+
+pub use audit::{AuditOp, AuditRecord, AuditStore};
+pub use autosave::{start_autosave, AutosaveHandle};
+pub use cache::{cached, CachedField};
+pub use codec::{Codec, JsonCodec, PlainTextCodec};
+#[cfg(feature = "compression")]
+pub use compression::{to_compressed_bytes, CompressionLevel};
+pub use config::{from_plain_text_with_config, read_decompressed, DbConfig};
+pub use csv::export_group_csv;
+pub use db::{
+    append_batch, append_data, clear, clear_group, compare_and_swap, contains_data, contains_group,
+    copy_group, copy_group_map, count_where, create_db, create_group, data_count,
+    data_names_matching, data_names_with_prefix, export_group, find, for_each_entry, get_data,
+    get_group, group_count, import_group, increment, increment_f64, insert_data, insert_if_absent,
+    insert_tagged, is_empty, list_data_names, list_data_names_page, list_groups, load_db,
+    merge_from, read_all, read_as, read_batch, read_data, read_or, read_or_insert_with, read_page,
+    read_prefix, read_tagged, remove_data_entry, remove_group, remove_prefix, remove_where,
+    rename_data, rename_group, render_db, retain_groups, snapshot_group, take_any, take_data,
+    total_entries, update_data, write_db, ImportConflict, MergeReport, Page,
+};
+pub use dedup::DedupDb;
+pub use diff::{diff, DbDiff, GroupDiff};
+pub use dir::{load_from_dir, save_changed_to_dir, save_to_dir};
+#[cfg(feature = "encryption")]
+pub use encryption::{
+    from_encrypted_bytes, from_encrypted_bytes_with_passphrase, to_encrypted_bytes,
+    to_encrypted_bytes_with_passphrase,
+};
+pub use error::{Error, ErrorKind};
+pub use events::{DbEvent, NotifyingStore, Subscription};
+pub use file::{
+    atomic_save, backup_path, load_from_file, load_latest_valid, load_with_codec, save_to_file,
+    save_with_backups, save_with_codec,
+};
+pub use format::{
+    from_bytes_auto, from_plain_text, from_plain_text_filtered, from_plain_text_lossy,
+    load_additional_group, read_from, to_plain_text, write_to, LoadWarning, FORMAT_VERSION,
+};
+pub use handle::{data_entry, group_handle, typed_group, DataEntry, GroupHandle, TypedGroup};
+pub use history::{enable_history, insert_data_with_history, read_history, revert};
+pub use hooks::{HookedStore, WriteHook, WriteHooks};
+#[cfg(feature = "http")]
+pub use http::serve_http;
+pub use index::IndexedStore;
+pub use json::{from_json_str, import_ndjson, insert_value, read_value, to_json_string, to_ndjson};
+pub use list::{list_len, list_pop, list_push};
+pub use lock::{lock_path, try_lock_timeout, FileGuard};
+pub use lru::LruStore;
+pub use manager::{
+    deep_clone, read_only, try_append_data, try_read_data, DbManager, ReadOnlyDb, SharedDb,
+};
+pub use meta::{get_meta, list_meta_keys, remove_meta, set_meta};
+pub use migration::{
+    apply_migrations, from_plain_text_with_migrations, schema_version, set_schema_version,
+    Migration,
+};
+pub use namespace::{namespace, NamespacedStore};
+pub use persist::{open_persistent, PersistentDb};
+pub use replication::{apply_event, sync_from_events, sync_from_ops, PublishingStore};
+pub use schema::SchemaStore;
+pub use search::{search, MatchedIn, SearchHit, SearchOpts};
+pub use stats::{
+    approximate_memory_bytes, db_stats, reserve_group, shrink_to_fit, sum_numeric, DbStats,
+    GroupStats, NumericStats,
+};
+pub use store::{KvStore, KvStoreExt, RecordingStore};
+#[cfg(feature = "tcp")]
+pub use tcp::serve_tcp;
+pub use timestamps::{
+    entry_metadata, insert_data_timed, insert_with_ttl, purge_expired, read_unexpired, EntryMeta,
+};
+pub use tombstone::{purge_tombstones, remove_data_entry_soft};
+pub use txn::{transaction, Txn};
+pub use undo::{OpRecord, UndoStore};
+pub use wal::{open_with_wal, Wal, WalOp};
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::db::{read_optional, store_optional};
+use crate::error::{Error, ErrorKind};
+use crate::json::json_scalar_to_string;
+use crate::Db;
+
+// A list is stored as a JSON array of strings, ["a","b"], so it stays one
+// line and readable in the file.
+fn read_list(db: &Db, group_name: &str, data_name: &str) -> Result<Vec<String>, Error> {
+    let Some(data) = read_optional::<String>(db, group_name, data_name)? else {
+        return Ok(Vec::new());
+    };
+    let not_a_list = || {
+        Error::new(
+            ErrorKind::Parse,
+            format!("'{}' in group '{}' is not a list", data_name, group_name),
+        )
+    };
+    let serde_json::Value::Array(items) =
+        serde_json::from_str::<serde_json::Value>(&data).map_err(|_| not_a_list())?
+    else {
+        return Err(not_a_list());
+    };
+    items
+        .into_iter()
+        .map(|item| json_scalar_to_string(item).ok_or_else(not_a_list))
+        .collect()
+}
+
+fn write_list(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    items: Vec<String>,
+) -> Result<(), Error> {
+    let items = items.into_iter().map(serde_json::Value::String).collect();
+    let data = serde_json::Value::Array(items).to_string();
+    store_optional(db, group_name, data_name, Some(&data))
+}
+
+/// Appends item to the list stored in the entry, starting a new list when the
+/// entry is missing, and returns the new length. An entry that isn't a list is
+/// an error.
+pub fn list_push<T: fmt::Display>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    item: &T,
+) -> Result<usize, Error> {
+    let mut items = read_list(db, group_name, data_name)?;
+    items.push(item.to_string());
+    let len = items.len();
+    write_list(db, group_name, data_name, items)?;
+    Ok(len)
+}
+
+/// Removes the last item and returns it parsed as T, None when the list is
+/// empty or the entry is missing. An item that doesn't parse stays in the list.
+pub fn list_pop<T: FromStr>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+) -> Result<Option<T>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let mut items = read_list(db, group_name, data_name)?;
+    let Some(item) = items.pop() else {
+        return Ok(None);
+    };
+    let value = item.parse::<T>().map_err(|why| {
+        Error::new(
+            ErrorKind::Parse,
+            format!(
+                "last item of '{}' in group '{}' could not be parsed: {}",
+                data_name, group_name, why
+            ),
+        )
+    })?;
+    write_list(db, group_name, data_name, items)?;
+    Ok(Some(value))
+}
+
+/// 0 when the entry is missing.
+pub fn list_len(db: &Db, group_name: &str, data_name: &str) -> Result<usize, Error> {
+    Ok(read_list(db, group_name, data_name)?.len())
+}
@@ -0,0 +1,103 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, ErrorKind};
+use crate::file::{atomic_write, load_from_file};
+use crate::format::write_plain_text;
+use crate::Db;
+
+/// Holds an advisory lock on `<path>.lock` so only one process at a time saves
+/// or loads the database at path. While it is held save_to_file, atomic_save
+/// and the other saves refuse to write path, save through the guard instead.
+/// The lock is released when dropped.
+pub struct FileGuard {
+    // Only kept open, the lock is released when it is closed.
+    _file: File,
+    db_path: PathBuf,
+}
+
+pub(crate) fn lock_file_path(path: &Path) -> PathBuf {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+/// Fails straight away if another process holds the lock.
+pub fn lock_path(path: impl AsRef<Path>) -> Result<FileGuard, Error> {
+    let db_path = path.as_ref().to_path_buf();
+    let lock_path = lock_file_path(&db_path);
+    // Not truncated on open, the holder's PID must survive until we own it.
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .map_err(|why| Error::io(&lock_path, why))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => return Err(locked(&db_path, &mut file)),
+        Err(fs::TryLockError::Error(why)) => return Err(Error::io(&lock_path, why)),
+    }
+    file.set_len(0)
+        .and_then(|_| write!(file, "{}", std::process::id()))
+        .map_err(|why| Error::io(&lock_path, why))?;
+    Ok(FileGuard { _file: file, db_path })
+}
+
+// Locked, with the holder's PID when it wrote one.
+fn locked(db_path: &Path, lock_file: &mut File) -> Error {
+    let mut holder = String::new();
+    let _ = lock_file.read_to_string(&mut holder);
+    let message = match holder.trim().parse::<u32>() {
+        Ok(pid) => format!("{} is locked by process {}", db_path.display(), pid),
+        Err(_) => format!("{} is locked by another process", db_path.display()),
+    };
+    Error::new(ErrorKind::Locked, message)
+}
+
+// Locked when some FileGuard holds path's lock, this process's own included,
+// so a save that doesn't go through the guard can't write under it.
+pub(crate) fn refuse_if_locked(path: &Path) -> Result<(), Error> {
+    let lock_path = lock_file_path(path);
+    let mut file = match File::open(&lock_path) {
+        Ok(file) => file,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(why) => return Err(Error::io(&lock_path, why)),
+    };
+    match file.try_lock_shared() {
+        Ok(()) => Ok(()),
+        Err(fs::TryLockError::WouldBlock) => Err(locked(path, &mut file)),
+        Err(fs::TryLockError::Error(why)) => Err(Error::io(&lock_path, why)),
+    }
+}
+
+/// Retries lock_path until it succeeds or timeout has passed.
+pub fn try_lock_timeout(path: impl AsRef<Path>, timeout: Duration) -> Result<FileGuard, Error> {
+    let path = path.as_ref();
+    let deadline = Instant::now() + timeout;
+    loop {
+        match lock_path(path) {
+            Err(why) if why.kind == ErrorKind::Locked && Instant::now() < deadline => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            result => return result,
+        }
+    }
+}
+
+impl FileGuard {
+    /// Saves db the way atomic_save does, which the lock refuses to anything
+    /// but the guard.
+    pub fn save(&self, db: &Db) -> Result<(), Error> {
+        atomic_write(&self.db_path, |writer| write_plain_text(db, writer))
+    }
+
+    /// Loads the database the lock is for.
+    pub fn load(&self) -> Result<Db, Error> {
+        load_from_file(&self.db_path)
+    }
+}
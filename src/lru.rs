@@ -0,0 +1,249 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::config::DbConfig;
+use crate::error::Error;
+use crate::store::KvStore;
+
+/// Wraps a store used as a cache and keeps it within the DbConfig's
+/// max_entries_per_group and max_total_bytes by removing the least recently
+/// used entries after each put. Reads and writes through the wrapper both
+/// count as a use. Evictions are made with the inner store's
+/// remove_data_entry, so a PublishingStore or RecordingStore underneath sees
+/// each one as a RemoveEntry.
+///
+/// The access order only lives in memory, entries already in the store start
+/// out least recently used in name order. get_raw updates it through a
+/// RefCell, so an LruStore can't be shared between threads.
+pub struct LruStore<S> {
+    inner: S,
+    max_entries_per_group: Option<usize>,
+    max_total_bytes: Option<usize>,
+    // Groups that are never evicted from and don't count towards the limits.
+    precious: HashSet<String>,
+    clock: Cell<u64>,
+    order: RefCell<Order>,
+}
+
+#[derive(Default)]
+struct Order {
+    // Access stamp of each tracked entry and its size in bytes.
+    tracked: HashMap<(String, String), (u64, usize)>,
+    by_stamp: BTreeMap<u64, (String, String)>,
+    entries_per_group: HashMap<String, usize>,
+    total_bytes: usize,
+}
+
+impl Order {
+    fn touch(&mut self, key: &(String, String), stamp: u64) {
+        if let Some((old_stamp, _)) = self.tracked.get_mut(key) {
+            self.by_stamp.remove(old_stamp);
+            *old_stamp = stamp;
+            self.by_stamp.insert(stamp, key.clone());
+        }
+    }
+
+    fn insert(&mut self, key: (String, String), bytes: usize, stamp: u64) {
+        self.forget(&key);
+        *self.entries_per_group.entry(key.0.clone()).or_default() += 1;
+        self.total_bytes += bytes;
+        self.by_stamp.insert(stamp, key.clone());
+        self.tracked.insert(key, (stamp, bytes));
+    }
+
+    fn forget(&mut self, key: &(String, String)) {
+        let Some((stamp, bytes)) = self.tracked.remove(key) else {
+            return;
+        };
+        self.by_stamp.remove(&stamp);
+        self.total_bytes -= bytes;
+        if let Some(entries) = self.entries_per_group.get_mut(&key.0) {
+            *entries -= 1;
+            if *entries == 0 {
+                self.entries_per_group.remove(&key.0);
+            }
+        }
+    }
+
+    fn forget_group(&mut self, group_name: &str) {
+        let keys: Vec<(String, String)> = self
+            .tracked
+            .keys()
+            .filter(|(tracked_group, _)| tracked_group == group_name)
+            .cloned()
+            .collect();
+        for key in keys {
+            self.forget(&key);
+        }
+    }
+
+    // The least recently used entry, of group_name when given, other than
+    // keep when given.
+    fn oldest(
+        &self,
+        group_name: Option<&str>,
+        keep: Option<&(String, String)>,
+    ) -> Option<(String, String)> {
+        self.by_stamp
+            .values()
+            .find(|key| {
+                Some(*key) != keep && group_name.is_none_or(|group_name| key.0 == group_name)
+            })
+            .cloned()
+    }
+}
+
+fn entry_bytes(data_name: &str, data: &str) -> usize {
+    data_name.len() + data.len()
+}
+
+impl<S: KvStore> LruStore<S> {
+    pub fn new(inner: S, config: &DbConfig) -> LruStore<S> {
+        let mut store = LruStore {
+            inner,
+            max_entries_per_group: config.max_entries_per_group,
+            max_total_bytes: config.max_total_bytes,
+            precious: HashSet::new(),
+            clock: Cell::new(0),
+            order: RefCell::new(Order::default()),
+        };
+        for group_name in store.inner.list_groups() {
+            store.track_group(&group_name);
+        }
+        store
+    }
+
+    /// Stops evicting from the group, or starts again, and takes it out of
+    /// or back into the limits. Doesn't evict by itself, the next put or
+    /// evict_to does.
+    pub fn set_precious(&mut self, group_name: &str, precious: bool) {
+        if precious {
+            self.precious.insert(group_name.to_string());
+            self.order.get_mut().forget_group(group_name);
+        } else if self.precious.remove(group_name) {
+            self.track_group(group_name);
+        }
+    }
+
+    /// Bytes of data_names and data in the groups that aren't precious.
+    pub fn total_bytes(&self) -> usize {
+        self.order.borrow().total_bytes
+    }
+
+    /// Evicts least recently used entries until at most target_bytes are
+    /// left, returning how many went.
+    pub fn evict_to(&mut self, target_bytes: usize) -> usize {
+        let mut evicted = 0;
+        while self.order.get_mut().total_bytes > target_bytes {
+            let Some(oldest) = self.order.get_mut().oldest(None, None) else {
+                break;
+            };
+            self.evict(&oldest);
+            evicted += 1;
+        }
+        evicted
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn stamp(&self) -> u64 {
+        let stamp = self.clock.get() + 1;
+        self.clock.set(stamp);
+        stamp
+    }
+
+    fn track_group(&mut self, group_name: &str) {
+        for data_name in self.inner.list_data_names(group_name).unwrap_or_default() {
+            if let Ok(data) = self.inner.get_raw(group_name, &data_name) {
+                let bytes = entry_bytes(&data_name, &data);
+                let stamp = self.stamp();
+                self.order
+                    .get_mut()
+                    .insert((group_name.to_string(), data_name), bytes, stamp);
+            }
+        }
+    }
+
+    fn evict(&mut self, key: &(String, String)) {
+        self.inner.remove_data_entry(&key.0, &key.1);
+        self.order.get_mut().forget(key);
+    }
+
+    // Never evicts the entry just written, even when it alone is over the
+    // limits.
+    fn enforce_limits(&mut self, written: &(String, String)) {
+        if let Some(max_entries) = self.max_entries_per_group {
+            while self.order.get_mut().entries_per_group.get(&written.0) > Some(&max_entries) {
+                let Some(oldest) = self.order.get_mut().oldest(Some(&written.0), Some(written))
+                else {
+                    break;
+                };
+                self.evict(&oldest);
+            }
+        }
+        if let Some(max_total_bytes) = self.max_total_bytes {
+            while self.order.get_mut().total_bytes > max_total_bytes {
+                let Some(oldest) = self.order.get_mut().oldest(None, Some(written)) else {
+                    break;
+                };
+                self.evict(&oldest);
+            }
+        }
+    }
+}
+
+impl<S: KvStore> KvStore for LruStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        self.inner.create_group(group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let bytes = entry_bytes(data_name, &data);
+        let replaced = self.inner.put(group_name, data_name, data)?;
+        if !self.precious.contains(group_name) {
+            let key = (group_name.to_string(), data_name.to_string());
+            let stamp = self.stamp();
+            self.order.get_mut().insert(key.clone(), bytes, stamp);
+            self.enforce_limits(&key);
+        }
+        Ok(replaced)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        let data = self.inner.get_raw(group_name, data_name)?;
+        let stamp = self.stamp();
+        self.order
+            .borrow_mut()
+            .touch(&(group_name.to_string(), data_name.to_string()), stamp);
+        Ok(data)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let removed = self.inner.remove_data_entry(group_name, data_name)?;
+        self.order
+            .get_mut()
+            .forget(&(group_name.to_string(), data_name.to_string()));
+        Some(removed)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.inner.remove_group(group_name)?;
+        self.order.get_mut().forget_group(group_name);
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
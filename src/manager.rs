@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+
+use crate::db::{
+    contains_data, contains_group, insert_data, list_data_names, list_groups, read_as, read_batch,
+};
+use crate::error::{Error, ErrorKind};
+use crate::format::to_plain_text;
+use crate::persist::{open_persistent, PersistentDb};
+use crate::Db;
+
+/// A handle to one database. Cloning it is cheap and every clone reads and
+/// writes the same PersistentDb, cloning the Db behind the lock is the
+/// independent copy, see deep_clone.
+pub type SharedDb = Arc<Mutex<PersistentDb>>;
+
+/// Opens the databases kept as `<name>.pdbr` in one directory and hands out
+/// one shared handle per name, so every part of a program sees the same Db.
+/// The map of handles has its own lock, never held while a file is read or
+/// written.
+pub struct DbManager {
+    base_dir: PathBuf,
+    open: Mutex<HashMap<String, SharedDb>>,
+}
+
+impl DbManager {
+    pub fn new(base_dir: impl Into<PathBuf>) -> DbManager {
+        DbManager {
+            base_dir: base_dir.into(),
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The handle of the database called name, loading it first when it
+    /// isn't open yet. A database without a file starts empty and gets one
+    /// on the first save. InvalidKey for names that aren't a plain file name.
+    pub fn open(&self, name: &str) -> Result<SharedDb, Error> {
+        if let Some(db) = self.handles().get(name) {
+            return Ok(db.clone());
+        }
+        let path = self.path_of(name)?;
+        let db = match open_persistent(&path) {
+            Ok(db) => db,
+            Err(why) if why.kind() == ErrorKind::NotFound => {
+                let mut db = Db::new();
+                db.entry("name".to_string())
+                    .or_default()
+                    .insert("name".to_string(), name.to_string());
+                PersistentDb::new(db, &path)
+            }
+            Err(why) => return Err(why),
+        };
+        // Another thread may have opened it while the file was read, the
+        // first handle in the map wins.
+        let mut handles = self.handles();
+        let db = handles
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(db)));
+        Ok(db.clone())
+    }
+
+    /// Saves every open database that changed. All of them are tried, the
+    /// first error is returned.
+    pub fn save_all(&self) -> Result<(), Error> {
+        let dbs: Vec<SharedDb> = self.handles().values().cloned().collect();
+        let mut first_error = None;
+        for db in dbs {
+            if let Err(why) = lock(&db).save_if_changed() {
+                first_error.get_or_insert(why);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Saves the database if it changed and forgets its handle, returning
+    /// false when it wasn't open. A failed save keeps the handle open so the
+    /// changes aren't lost. Handles already given out stay usable.
+    pub fn close(&self, name: &str) -> Result<bool, Error> {
+        let Some(db) = self.handles().get(name).cloned() else {
+            return Ok(false);
+        };
+        lock(&db).save_if_changed()?;
+        let mut handles = self.handles();
+        // Only if nobody closed and reopened it while it was saved.
+        if handles.get(name).is_some_and(|open| Arc::ptr_eq(open, &db)) {
+            handles.remove(name);
+        }
+        Ok(true)
+    }
+
+    /// The names of the open databases, sorted.
+    pub fn list_open(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.handles().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn handles(&self) -> MutexGuard<'_, HashMap<String, SharedDb>> {
+        lock(&self.open)
+    }
+
+    fn path_of(&self, name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(Error::new(
+                ErrorKind::InvalidKey,
+                format!("'{}' can't be used as a database file name", name),
+            ));
+        }
+        Ok(self.base_dir.join(format!("{}.pdbr", name)))
+    }
+}
+
+/// A copy of the data behind the handle that later writes through the handle
+/// don't change, like a snapshot for a background export. The lock is held
+/// only while the Db is cloned.
+pub fn deep_clone(db: &SharedDb) -> Db {
+    Db::clone(&lock(db))
+}
+
+/// read_as that fails with WouldBlock instead of waiting when another handle
+/// holds the lock, for callers that can't afford to wait.
+pub fn try_read_data<T: FromStr>(
+    db: &SharedDb,
+    group_name: &str,
+    data_name: &str,
+) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    let db = try_lock(db)?;
+    read_as(&db, group_name, data_name)
+}
+
+/// insert_data, creating the group when needed, that fails with WouldBlock
+/// instead of waiting for the lock. The PersistentDb saves the write like any
+/// other.
+pub fn try_append_data<T: fmt::Display>(
+    db: &SharedDb,
+    group_name: &str,
+    data_name: &str,
+    data: &T,
+) -> Result<(), Error> {
+    let mut db = try_lock(db)?;
+    insert_data(&mut db, group_name, data_name, data.to_string(), true)?;
+    Ok(())
+}
+
+fn try_lock(db: &SharedDb) -> Result<MutexGuard<'_, PersistentDb>, Error> {
+    match db.try_lock() {
+        Ok(guard) => Ok(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => Err(Error::new(
+            ErrorKind::WouldBlock,
+            "the database is locked by another handle".to_string(),
+        )),
+    }
+}
+
+/// A handle that can only read, for code that shouldn't write to the database.
+/// It shares the Db with the handle it came from, so it sees every write made
+/// through that handle, see snapshot for a copy that doesn't.
+#[derive(Clone)]
+pub struct ReadOnlyDb {
+    db: SharedDb,
+}
+
+/// A read only handle to the same database as db.
+pub fn read_only(db: &SharedDb) -> ReadOnlyDb {
+    ReadOnlyDb { db: db.clone() }
+}
+
+impl ReadOnlyDb {
+    /// The entry parsed as T, like read_as.
+    pub fn read_data<T: FromStr>(&self, group_name: &str, data_name: &str) -> Result<T, Error>
+    where
+        T::Err: fmt::Display,
+    {
+        read_as(&lock(&self.db), group_name, data_name)
+    }
+
+    /// Like read_batch, every entry is read under the same lock.
+    pub fn read_batch<T: FromStr>(
+        &self,
+        group_name: &str,
+        data_names: &[&str],
+    ) -> Result<Vec<Option<T>>, Error>
+    where
+        T::Err: fmt::Display,
+    {
+        read_batch(&lock(&self.db), group_name, data_names)
+    }
+
+    pub fn list_groups(&self) -> Vec<String> {
+        list_groups(&lock(&self.db))
+    }
+
+    pub fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        list_data_names(&lock(&self.db), group_name)
+    }
+
+    pub fn contains_group(&self, group_name: &str) -> bool {
+        contains_group(&lock(&self.db), group_name)
+    }
+
+    pub fn contains_data(&self, group_name: &str, data_name: &str) -> bool {
+        contains_data(&lock(&self.db), group_name, data_name)
+    }
+
+    /// A copy as of now that later writes don't change, like deep_clone.
+    pub fn snapshot(&self) -> Db {
+        deep_clone(&self.db)
+    }
+
+    /// The database in the .pdbr format, like to_plain_text.
+    pub fn to_plain_text(&self) -> String {
+        to_plain_text(&lock(&self.db))
+    }
+}
+
+// A panic while a handle was locked doesn't make the Db unusable, the same as
+// serve_tcp treats it.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
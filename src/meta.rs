@@ -0,0 +1,61 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, ErrorKind};
+use crate::Db;
+
+// Application keys get their own prefix in the name group, so they can't
+// collide with the database name, the schema version or entry metadata.
+const META_PREFIX: &str = "meta:";
+
+/// Stores value under key in the database's metadata, saved with it but
+/// outside every group. Returns the text it replaced.
+pub fn set_meta<T: fmt::Display + ?Sized>(db: &mut Db, key: &str, value: &T) -> Option<String> {
+    db.entry("name".to_string())
+        .or_default()
+        .insert(format!("{}{}", META_PREFIX, key), value.to_string())
+}
+
+/// The metadata under key parsed as T, None when it was never set, which
+/// includes every file from before metadata existed.
+pub fn get_meta<T: FromStr>(db: &Db, key: &str) -> Result<Option<T>, Error>
+where
+    T::Err: fmt::Display,
+{
+    let Some(value) = db
+        .get("name")
+        .and_then(|inner| inner.get(&format!("{}{}", META_PREFIX, key)))
+    else {
+        return Ok(None);
+    };
+    value.parse::<T>().map(Some).map_err(|why| {
+        Error::new(
+            ErrorKind::Parse,
+            format!(
+                "metadata '{}' ({} bytes) could not be parsed: {}",
+                key,
+                value.len(),
+                why
+            ),
+        )
+    })
+}
+
+/// The text that was stored under key, None when nothing was.
+pub fn remove_meta(db: &mut Db, key: &str) -> Option<String> {
+    db.get_mut("name")?
+        .remove(&format!("{}{}", META_PREFIX, key))
+}
+
+/// Every key set_meta stored, sorted.
+pub fn list_meta_keys(db: &Db) -> Vec<String> {
+    let mut keys: Vec<String> = db
+        .get("name")
+        .into_iter()
+        .flat_map(|inner| inner.keys())
+        .filter_map(|key| key.strip_prefix(META_PREFIX))
+        .map(str::to_string)
+        .collect();
+    keys.sort();
+    keys
+}
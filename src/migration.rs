@@ -0,0 +1,92 @@
+use crate::error::{Error, ErrorKind};
+use crate::format::from_plain_text;
+use crate::Db;
+
+/// The application's schema version, kept as "schema" in the name group so it
+/// is saved with the database. 0 when it was never set.
+pub fn schema_version(db: &Db) -> u32 {
+    db.get("name")
+        .and_then(|inner| inner.get("schema"))
+        .and_then(|version| version.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Usually left to apply_migrations, which sets it after each migration.
+pub fn set_schema_version(db: &mut Db, version: u32) {
+    db.entry("name".to_string())
+        .or_default()
+        .insert("schema".to_string(), version.to_string());
+}
+
+/// Moves a database from one schema version to a later one.
+pub struct Migration {
+    from_version: u32,
+    to_version: u32,
+    migrate: fn(&mut Db) -> Result<(), Error>,
+}
+
+impl Migration {
+    /// migrate runs on databases at from_version and leaves them at
+    /// to_version, which has to be later.
+    pub fn new(from_version: u32, to_version: u32, migrate: fn(&mut Db) -> Result<(), Error>) -> Migration {
+        Migration {
+            from_version,
+            to_version,
+            migrate,
+        }
+    }
+}
+
+/// Runs the migration starting at the current schema version, then the one
+/// starting where that left off and so on, recording each new version. A
+/// version no migration starts at is fine once past the last one, before
+/// that it is a gap and fails.
+pub fn apply_migrations(db: &mut Db, migrations: &[Migration]) -> Result<(), Error> {
+    loop {
+        let version = schema_version(db);
+        let Some((index, migration)) = migrations
+            .iter()
+            .enumerate()
+            .find(|(_, migration)| migration.from_version == version)
+        else {
+            let next = migrations
+                .iter()
+                .map(|migration| migration.from_version)
+                .filter(|from_version| *from_version > version)
+                .min();
+            return match next {
+                Some(next) => Err(Error::new(
+                    ErrorKind::Migration,
+                    format!("no migration from version {}, the next one starts at {}", version, next),
+                )),
+                None => Ok(()),
+            };
+        };
+        if migration.to_version <= migration.from_version {
+            return Err(Error::new(
+                ErrorKind::Migration,
+                format!(
+                    "migration {} goes from version {} back to {}",
+                    index, migration.from_version, migration.to_version
+                ),
+            ));
+        }
+        (migration.migrate)(db).map_err(|why| {
+            Error::new(
+                ErrorKind::Migration,
+                format!(
+                    "migration {} ({} -> {}) failed: {}",
+                    index, migration.from_version, migration.to_version, why.message
+                ),
+            )
+        })?;
+        set_schema_version(db, migration.to_version);
+    }
+}
+
+/// from_plain_text, then apply_migrations on what it read.
+pub fn from_plain_text_with_migrations(contents: &str, migrations: &[Migration]) -> Result<Db, Error> {
+    let mut db = from_plain_text(contents)?;
+    apply_migrations(&mut db, migrations)?;
+    Ok(db)
+}
@@ -0,0 +1,89 @@
+use crate::error::{Error, ErrorKind};
+use crate::store::KvStore;
+
+const SEPARATOR: char = ':';
+
+/// A view of a store confined to the groups named prefix:group_name, seen
+/// without the prefix. Group names containing the separator are refused, so
+/// nothing written through one namespace can reach a group of another.
+pub struct NamespacedStore<S> {
+    inner: S,
+    prefix: String,
+}
+
+/// A prefix like a:b is a namespace nested in a, whose list_groups leaves its
+/// groups out.
+pub fn namespace<S: KvStore>(inner: S, prefix: &str) -> NamespacedStore<S> {
+    NamespacedStore {
+        inner,
+        prefix: format!("{}{}", prefix, SEPARATOR),
+    }
+}
+
+impl<S: KvStore> NamespacedStore<S> {
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn inner_name(&self, group_name: &str) -> Result<String, Error> {
+        if group_name.contains(SEPARATOR) {
+            return Err(Error::new(
+                ErrorKind::InvalidKey,
+                format!(
+                    "group name '{}' contains '{}', which namespaces use",
+                    group_name, SEPARATOR
+                ),
+            ));
+        }
+        Ok(format!("{}{}", self.prefix, group_name))
+    }
+}
+
+impl<S: KvStore> KvStore for NamespacedStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        match self.inner_name(group_name) {
+            Ok(group_name) => self.inner.create_group(&group_name),
+            Err(_) => false,
+        }
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let group_name = self.inner_name(group_name)?;
+        self.inner.put(&group_name, data_name, data)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(&self.inner_name(group_name)?, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let group_name = self.inner_name(group_name).ok()?;
+        self.inner.remove_data_entry(&group_name, data_name)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let group_name = self.inner_name(group_name).ok()?;
+        self.inner.remove_group(&group_name)
+    }
+
+    // Groups of nested namespaces have the separator left after the prefix
+    // and belong to those, not this one.
+    fn list_groups(&self) -> Vec<String> {
+        self.inner
+            .list_groups()
+            .iter()
+            .filter_map(|group_name| group_name.strip_prefix(&self.prefix))
+            .filter(|group_name| !group_name.contains(SEPARATOR))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(&self.inner_name(group_name)?)
+    }
+}
@@ -0,0 +1,112 @@
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::file::{atomic_save, load_from_file};
+use crate::format::to_plain_text;
+use crate::Db;
+
+/// A database that saves itself to its file with atomic_save on shutdown, or
+/// when dropped if shutdown was never called. Derefs to the Db.
+///
+/// Every mutable borrow of the Db counts as a change, whether or not anything
+/// was written through it, so saves are skipped only when the Db certainly
+/// hasn't changed.
+pub struct PersistentDb {
+    db: Db,
+    path: PathBuf,
+    shut_down: bool,
+    generation: u64,
+    // None until the Db is known to be what the file holds.
+    saved_generation: Option<u64>,
+}
+
+/// Loads the database at path, see PersistentDb::new for one that isn't saved
+/// yet.
+pub fn open_persistent(path: impl AsRef<Path>) -> Result<PersistentDb, Error> {
+    let db = load_from_file(&path)?;
+    let mut persistent = PersistentDb::new(db, path);
+    persistent.saved_generation = Some(0);
+    Ok(persistent)
+}
+
+impl PersistentDb {
+    pub fn new(db: Db, path: impl AsRef<Path>) -> PersistentDb {
+        PersistentDb {
+            db,
+            path: path.as_ref().to_path_buf(),
+            shut_down: false,
+            generation: 0,
+            saved_generation: None,
+        }
+    }
+
+    /// The file save writes to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Starts at 0 and goes up with every change.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Saves with atomic_save whether or not anything changed.
+    pub fn save(&mut self) -> Result<(), Error> {
+        atomic_save(&self.db, &self.path)?;
+        self.saved_generation = Some(self.generation);
+        Ok(())
+    }
+
+    /// Saves only when the Db changed since it was loaded or last saved,
+    /// returning whether it did.
+    pub fn save_if_changed(&mut self) -> Result<bool, Error> {
+        if self.saved_generation == Some(self.generation) {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// The Db as plain text with its generation, None when the generation is
+    /// still last_seen.
+    pub fn to_plain_text_if_changed(&self, last_seen: u64) -> Option<(u64, String)> {
+        if self.generation == last_seen {
+            return None;
+        }
+        Some((self.generation, to_plain_text(&self.db)))
+    }
+
+    /// The final save, with its error returned instead of only printed like
+    /// the one on drop. Skipped when nothing changed.
+    pub fn shutdown(mut self) -> Result<(), Error> {
+        self.shut_down = true;
+        self.save_if_changed().map(|_| ())
+    }
+}
+
+impl Deref for PersistentDb {
+    type Target = Db;
+
+    fn deref(&self) -> &Db {
+        &self.db
+    }
+}
+
+impl DerefMut for PersistentDb {
+    fn deref_mut(&mut self) -> &mut Db {
+        self.generation += 1;
+        &mut self.db
+    }
+}
+
+impl Drop for PersistentDb {
+    fn drop(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        if let Err(why) = self.save_if_changed() {
+            eprintln!("couldn't save {} on drop: {}", self.path.display(), why);
+        }
+    }
+}
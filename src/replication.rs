@@ -0,0 +1,168 @@
+use std::sync::mpsc::{Receiver, Sender};
+
+use crate::db::{create_group, insert_data, remove_data_entry, remove_group};
+use crate::error::{Error, ErrorKind};
+use crate::events::{DbEvent, Subscription};
+use crate::store::KvStore;
+use crate::wal::WalOp;
+use crate::Db;
+
+/// Wraps the primary's store and sends every write that changed something
+/// to a follower as a WalOp, after it is made. The ops arrive in the order
+/// the writes were made, the channel is unbounded so a slow follower never
+/// loses any. A follower that fell out of sync some other way starts over
+/// from a clone of the primary's Db. See sync_from_events for following a
+/// NotifyingStore instead, which never makes the primary wait or hold on to
+/// ops for a slow follower.
+pub struct PublishingStore<S> {
+    inner: S,
+    ops: Sender<WalOp>,
+    follower_gone: bool,
+}
+
+impl<S: KvStore> PublishingStore<S> {
+    pub fn new(inner: S, ops: Sender<WalOp>) -> PublishingStore<S> {
+        PublishingStore {
+            inner,
+            ops,
+            follower_gone: false,
+        }
+    }
+
+    /// The receiving end was dropped, writes since then weren't sent. They
+    /// are still made.
+    pub fn follower_gone(&self) -> bool {
+        self.follower_gone
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn publish(&mut self, op: WalOp) {
+        if !self.follower_gone && self.ops.send(op).is_err() {
+            self.follower_gone = true;
+        }
+    }
+}
+
+impl<S: KvStore> KvStore for PublishingStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        let created = self.inner.create_group(group_name);
+        if created {
+            self.publish(WalOp::CreateGroup {
+                group_name: group_name.to_string(),
+            });
+        }
+        created
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let previous = self.inner.put(group_name, data_name, data.clone())?;
+        self.publish(WalOp::Append {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+            data,
+        });
+        Ok(previous)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let removed = self.inner.remove_data_entry(group_name, data_name)?;
+        self.publish(WalOp::RemoveEntry {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+        });
+        Some(removed)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.inner.remove_group(group_name)?;
+        self.publish(WalOp::RemoveGroup {
+            group_name: group_name.to_string(),
+        });
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
+
+/// The follower side: applies ops to db in the order they arrive until every
+/// sender is dropped, returning how many it applied. Ops WalOp::apply refuses
+/// are skipped, a PublishingStore never sends them. Blocks, run it on the
+/// follower's own thread.
+pub fn sync_from_ops(db: &mut Db, ops: &Receiver<WalOp>) -> usize {
+    let mut applied = 0;
+    for op in ops {
+        if op.apply(db).is_ok() {
+            applied += 1;
+        }
+    }
+    applied
+}
+
+/// Applies one event from a NotifyingStore to db. Lagged for DbEvent::Missed,
+/// db can't catch up from the events that are left.
+pub fn apply_event(db: &mut Db, event: &DbEvent) -> Result<(), Error> {
+    match event {
+        DbEvent::GroupCreated { group_name } => {
+            create_group(db, group_name);
+        }
+        DbEvent::GroupRemoved { group_name } => {
+            remove_group(db, group_name);
+        }
+        DbEvent::DataWritten {
+            group_name,
+            data_name,
+            data,
+        } => {
+            insert_data(db, group_name, data_name, data.clone(), true)?;
+        }
+        DbEvent::DataRemoved {
+            group_name,
+            data_name,
+        } => {
+            remove_data_entry(db, group_name, data_name);
+        }
+        DbEvent::Missed(missed) => {
+            return Err(Error::new(
+                ErrorKind::Lagged,
+                format!("missed {} events of the primary", missed),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// The follower side for a NotifyingStore subscription: applies events to db
+/// in the order the writes were made until the store is dropped, returning
+/// how many it applied. Blocks, run it on the follower's own thread.
+///
+/// A follower that falls behind by more than the subscription buffers misses
+/// events, then this stops with Lagged after applying everything before the
+/// gap, also when the gap was at the end. The follower has to start over from a clone of the primary's Db and
+/// a new subscription, made while holding the primary so no write falls
+/// between them.
+pub fn sync_from_events(db: &mut Db, events: &Subscription) -> Result<usize, Error> {
+    let mut applied = 0;
+    for event in events.iter() {
+        apply_event(db, &event)?;
+        applied += 1;
+    }
+    Ok(applied)
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::error::{Error, ErrorKind};
+use crate::store::KvStore;
+
+// Whether some data parses as the group's type.
+type Validator = Box<dyn Fn(&str) -> bool>;
+
+/// Wraps a store and refuses writes to a group whose data doesn't parse as
+/// the type registered for it, with a SchemaViolation error. Schemas only
+/// live in memory, set them again after loading.
+pub struct SchemaStore<S> {
+    inner: S,
+    schemas: HashMap<String, (&'static str, Validator)>,
+}
+
+impl<S: KvStore> SchemaStore<S> {
+    pub fn new(inner: S) -> SchemaStore<S> {
+        SchemaStore {
+            inner,
+            schemas: HashMap::new(),
+        }
+    }
+
+    /// Every later put to the group has to parse as T. Entries already there
+    /// aren't checked, see validate_group.
+    pub fn set_group_schema<T: FromStr + 'static>(&mut self, group_name: &str) {
+        let validator: Validator = Box::new(|data| data.parse::<T>().is_ok());
+        self.schemas.insert(
+            group_name.to_string(),
+            (std::any::type_name::<T>(), validator),
+        );
+    }
+
+    /// False when the group had no schema.
+    pub fn remove_group_schema(&mut self, group_name: &str) -> bool {
+        self.schemas.remove(group_name).is_some()
+    }
+
+    /// The group's data_names whose data doesn't parse as its schema, sorted.
+    /// Empty when the group has no schema or doesn't exist.
+    pub fn validate_group(&self, group_name: &str) -> Vec<String> {
+        let Some((_, validator)) = self.schemas.get(group_name) else {
+            return Vec::new();
+        };
+        let data_names = self.inner.list_data_names(group_name).unwrap_or_default();
+        data_names
+            .into_iter()
+            .filter(|data_name| {
+                self.inner
+                    .get_raw(group_name, data_name)
+                    .is_ok_and(|data| !validator(&data))
+            })
+            .collect()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: KvStore> KvStore for SchemaStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        self.inner.create_group(group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        if let Some((type_name, validator)) = self.schemas.get(group_name) {
+            if !validator(&data) {
+                return Err(Error::new(
+                    ErrorKind::SchemaViolation,
+                    format!(
+                        "'{}' in group '{}' ({} bytes) is not a {}",
+                        data_name,
+                        group_name,
+                        data.len(),
+                        type_name
+                    ),
+                ));
+            }
+        }
+        self.inner.put(group_name, data_name, data)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        self.inner.remove_data_entry(group_name, data_name)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        self.inner.remove_group(group_name)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+use crate::db::list_groups;
+use crate::timestamps::entry_meta_value;
+use crate::Db;
+
+// Characters of context kept on each side of a match in a snippet.
+const SNIPPET_CONTEXT: usize = 20;
+
+/// Which groups search looks in and how many hits it returns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchOpts {
+    /// Every group when None.
+    pub group_names: Option<Vec<String>>,
+    /// No limit when None.
+    pub limit: Option<usize>,
+}
+
+/// Whether the query was found in the data_name or the data. A hit in both
+/// is reported as DataName.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchedIn {
+    DataName,
+    Data,
+}
+
+/// One entry search found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub group_name: String,
+    pub data_name: String,
+    pub matched_in: MatchedIn,
+    /// The match with some text around it, ... where that was cut.
+    pub snippet: String,
+}
+
+/// Every data_name and data containing needle, ignoring case, sorted by group
+/// then data_name with a data_name match before a data match. Data stored
+/// compressed is skipped, it isn't text that can match.
+pub fn search(db: &Db, needle: &str, opts: &SearchOpts) -> Vec<SearchHit> {
+    let group_names = match &opts.group_names {
+        Some(group_names) => {
+            let mut group_names = group_names.clone();
+            group_names.sort();
+            group_names.dedup();
+            group_names
+        }
+        None => list_groups(db),
+    };
+    let limit = opts.limit.unwrap_or(usize::MAX);
+    let mut hits = Vec::new();
+    for group_name in group_names {
+        let Some(group) = db.get(&group_name).filter(|_| group_name != "name") else {
+            continue;
+        };
+        let mut entries: Vec<(&String, &String)> = group.iter().collect();
+        entries.sort();
+        for (data_name, data) in entries {
+            let compressed = entry_meta_value(db, "compressed", &group_name, data_name).is_some();
+            let mut candidates = vec![(MatchedIn::DataName, data_name)];
+            if !compressed {
+                candidates.push((MatchedIn::Data, data));
+            }
+            for (matched_in, text) in candidates {
+                if hits.len() >= limit {
+                    return hits;
+                }
+                if let Some(found) = find_ignore_case(text, needle) {
+                    hits.push(SearchHit {
+                        group_name: group_name.clone(),
+                        data_name: data_name.clone(),
+                        matched_in,
+                        snippet: snippet(text, found),
+                    });
+                }
+            }
+        }
+    }
+    hits
+}
+
+// Byte range of the first match in haystack, comparing lowercased chars.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<Range<usize>> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    for (start, _) in haystack.char_indices() {
+        let mut matched = 0;
+        for (offset, c) in haystack[start..].char_indices() {
+            if matched == needle.len() {
+                return Some(start..start + offset);
+            }
+            for lower in c.to_lowercase() {
+                if needle.get(matched) != Some(&lower) {
+                    matched = usize::MAX;
+                    break;
+                }
+                matched += 1;
+            }
+            if matched == usize::MAX {
+                break;
+            }
+        }
+        if matched == needle.len() {
+            return Some(start..haystack.len());
+        }
+    }
+    needle.is_empty().then_some(0..0)
+}
+
+fn snippet(text: &str, found: Range<usize>) -> String {
+    let before: Vec<(usize, char)> = text[..found.start].char_indices().collect();
+    let start = before
+        .len()
+        .checked_sub(SNIPPET_CONTEXT)
+        .map_or(0, |i| before[i].0);
+    let end = text[found.end..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(text.len(), |(i, _)| found.end + i);
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    snippet.push_str(&text[start..end]);
+    if end < text.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
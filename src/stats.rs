@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+
+use serde_json::{json, Value};
+
+use crate::db::get_group;
+use crate::error::Error;
+use crate::timestamps::entry_meta_value;
+use crate::Db;
+
+/// Sizes of one group, data_bytes counting only the data, not data_names.
+/// Data DbConfig::insert compressed counts as stored in data_bytes and as it
+/// was before compression in uncompressed_bytes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupStats {
+    pub group_name: String,
+    pub entries: usize,
+    pub data_bytes: usize,
+    pub compressed_entries: usize,
+    pub uncompressed_bytes: usize,
+    /// data_name and length of the longest data, the first in sorted order on
+    /// a tie.
+    pub largest: Option<(String, usize)>,
+}
+
+/// Sizes of every group but name, sorted by group name, and their totals.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbStats {
+    pub groups: Vec<GroupStats>,
+    pub total_entries: usize,
+    pub total_data_bytes: usize,
+    pub total_uncompressed_bytes: usize,
+}
+
+impl fmt::Display for DbStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} groups, {} entries, {} bytes",
+            self.groups.len(),
+            self.total_entries,
+            self.total_data_bytes
+        )?;
+        if self.total_uncompressed_bytes != self.total_data_bytes {
+            write!(f, " ({} uncompressed)", self.total_uncompressed_bytes)?;
+        }
+        for group in &self.groups {
+            write!(
+                f,
+                "\n  {}: {} entries, {} bytes",
+                group.group_name, group.entries, group.data_bytes
+            )?;
+            if group.compressed_entries > 0 {
+                write!(
+                    f,
+                    " ({} uncompressed, {} entries compressed)",
+                    group.uncompressed_bytes, group.compressed_entries
+                )?;
+            }
+            if let Some((data_name, len)) = &group.largest {
+                write!(f, ", largest {} ({} bytes)", data_name, len)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DbStats {
+    /// The same numbers as a JSON object, for metrics pipelines. groups is an
+    /// array in the same order, largest is null or {data_name, bytes}.
+    pub fn to_json(&self) -> String {
+        let groups: Vec<Value> = self
+            .groups
+            .iter()
+            .map(|group| {
+                json!({
+                    "group_name": group.group_name,
+                    "entries": group.entries,
+                    "data_bytes": group.data_bytes,
+                    "compressed_entries": group.compressed_entries,
+                    "uncompressed_bytes": group.uncompressed_bytes,
+                    "largest": group.largest.as_ref().map(|(data_name, len)| {
+                        json!({ "data_name": data_name, "bytes": len })
+                    }),
+                })
+            })
+            .collect();
+        json!({
+            "groups": groups,
+            "total_entries": self.total_entries,
+            "total_data_bytes": self.total_data_bytes,
+            "total_uncompressed_bytes": self.total_uncompressed_bytes,
+        })
+        .to_string()
+    }
+}
+
+/// One pass over db, nothing is copied but the names in the result.
+pub fn db_stats(db: &Db) -> DbStats {
+    let mut group_names: Vec<&String> = db.keys().filter(|key| *key != "name").collect();
+    group_names.sort();
+    let mut stats = DbStats::default();
+    for group_name in group_names {
+        let group = &db[group_name];
+        let mut group_stats = GroupStats {
+            group_name: group_name.clone(),
+            entries: group.len(),
+            ..GroupStats::default()
+        };
+        let mut largest: Option<(&String, usize)> = None;
+        for (data_name, data) in group {
+            group_stats.data_bytes += data.len();
+            match entry_meta_value(db, "compressed", group_name, data_name) {
+                Some(mark) => {
+                    group_stats.compressed_entries += 1;
+                    // Marks from before the length was kept only say zlib.
+                    group_stats.uncompressed_bytes += mark
+                        .strip_prefix("zlib:")
+                        .and_then(|len| len.parse().ok())
+                        .unwrap_or(data.len());
+                }
+                None => group_stats.uncompressed_bytes += data.len(),
+            }
+            let is_larger = match largest {
+                Some((largest_name, largest_len)) => {
+                    data.len() > largest_len
+                        || (data.len() == largest_len && data_name < largest_name)
+                }
+                None => true,
+            };
+            if is_larger {
+                largest = Some((data_name, data.len()));
+            }
+        }
+        group_stats.largest = largest.map(|(data_name, len)| (data_name.clone(), len));
+        stats.total_entries += group_stats.entries;
+        stats.total_data_bytes += group_stats.data_bytes;
+        stats.total_uncompressed_bytes += group_stats.uncompressed_bytes;
+        stats.groups.push(group_stats);
+    }
+    stats
+}
+
+/// A rough guess at the heap the Db holds: the capacity of every string, plus
+/// a String and a hash per map slot for each map's capacity. The name group is
+/// included.
+pub fn approximate_memory_bytes(db: &Db) -> usize {
+    let slot = |value_size: usize| size_of::<String>() + value_size + size_of::<u64>();
+    let mut bytes = db.capacity() * slot(size_of::<HashMap<String, String>>());
+    for (group_name, group) in db {
+        bytes += group_name.capacity() + group.capacity() * slot(size_of::<String>());
+        for (data_name, data) in group {
+            bytes += data_name.capacity() + data.capacity();
+        }
+    }
+    bytes
+}
+
+/// Gives back spare capacity of every map and string, after removing most of
+/// a database for example.
+pub fn shrink_to_fit(db: &mut Db) {
+    db.shrink_to_fit();
+    for group in db.values_mut() {
+        group.shrink_to_fit();
+        for data in group.values_mut() {
+            data.shrink_to_fit();
+        }
+    }
+}
+
+/// Makes room for additional more entries in the group before a bulk insert.
+pub fn reserve_group(db: &mut Db, group_name: &str, additional: usize) -> Result<(), Error> {
+    get_group(db, group_name)?;
+    if let Some(group) = db.get_mut(group_name) {
+        group.reserve(additional);
+    }
+    Ok(())
+}
+
+/// What sum_numeric found. min, max and sum are all f64, integer_sum is the
+/// exact sum, None when a value was a float or the sum overflowed i128.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumericStats {
+    pub count: usize,
+    /// Entries that aren't a finite number.
+    pub skipped: usize,
+    pub sum: f64,
+    pub integer_sum: Option<i128>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl NumericStats {
+    pub fn avg(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+/// Sums the group's entries that are integers or floats, only those whose
+/// data_name starts with prefix when there is one. Other entries are counted
+/// in skipped.
+pub fn sum_numeric(db: &Db, group_name: &str, prefix: Option<&str>) -> Result<NumericStats, Error> {
+    let group = get_group(db, group_name)?;
+    let mut stats = NumericStats {
+        integer_sum: Some(0),
+        ..NumericStats::default()
+    };
+    for (data_name, data) in group {
+        if prefix.is_some_and(|prefix| !data_name.starts_with(prefix)) {
+            continue;
+        }
+        let integer = data.parse::<i128>().ok();
+        let value = match integer {
+            Some(integer) => integer as f64,
+            None => match data.parse::<f64>() {
+                Ok(value) if value.is_finite() => value,
+                _ => {
+                    stats.skipped += 1;
+                    continue;
+                }
+            },
+        };
+        stats.count += 1;
+        stats.sum += value;
+        stats.integer_sum = stats
+            .integer_sum
+            .zip(integer)
+            .and_then(|(sum, integer)| sum.checked_add(integer));
+        stats.min = Some(stats.min.map_or(value, |min| min.min(value)));
+        stats.max = Some(stats.max.map_or(value, |max| max.max(value)));
+    }
+    Ok(stats)
+}
@@ -0,0 +1,204 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::db::{
+    create_group, get_data, insert_data, list_data_names, list_groups, parse_error,
+    remove_data_entry, remove_group,
+};
+use crate::error::Error;
+use crate::persist::PersistentDb;
+use crate::wal::WalOp;
+use crate::Db;
+
+/// The core operations as a trait, so code can be written against any store
+/// and tested against a plain in memory Db. Object safe, typed reads are in
+/// KvStoreExt.
+pub trait KvStore {
+    /// False when the group was already there.
+    fn create_group(&mut self, group_name: &str) -> bool;
+
+    /// Creates the group if needed, returns the data it replaced.
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error>;
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error>;
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String>;
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize>;
+
+    fn list_groups(&self) -> Vec<String>;
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error>;
+}
+
+/// Typed reads over get_raw for every KvStore, trait objects included.
+pub trait KvStoreExt: KvStore {
+    fn get<T: FromStr>(&self, group_name: &str, data_name: &str) -> Result<T, Error>
+    where
+        T::Err: fmt::Display,
+    {
+        parse_as(self.get_raw(group_name, data_name)?, group_name, data_name)
+    }
+}
+
+impl<S: KvStore + ?Sized> KvStoreExt for S {}
+
+fn parse_as<T: FromStr>(data: String, group_name: &str, data_name: &str) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    data.parse::<T>()
+        .map_err(|why| parse_error(group_name, data_name, &data, why))
+}
+
+impl KvStore for Db {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        create_group(self, group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        insert_data(self, group_name, data_name, data, true)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        get_data(self, group_name, data_name).map(Cow::into_owned)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        remove_data_entry(self, group_name, data_name)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        remove_group(self, group_name)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        list_groups(self)
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        list_data_names(self, group_name)
+    }
+}
+
+// Through the Deref impls, so changes count towards the next save.
+impl KvStore for PersistentDb {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        KvStore::create_group(&mut **self, group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        KvStore::put(&mut **self, group_name, data_name, data)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        KvStore::get_raw(&**self, group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        KvStore::remove_data_entry(&mut **self, group_name, data_name)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        KvStore::remove_group(&mut **self, group_name)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        KvStore::list_groups(&**self)
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        KvStore::list_data_names(&**self, group_name)
+    }
+}
+
+/// Wraps a store and records every write made through it, successful or
+/// not, for tests to assert on. Reads aren't recorded.
+pub struct RecordingStore<S> {
+    inner: S,
+    ops: Vec<WalOp>,
+}
+
+impl<S: KvStore> RecordingStore<S> {
+    pub fn new(inner: S) -> RecordingStore<S> {
+        RecordingStore {
+            inner,
+            ops: Vec::new(),
+        }
+    }
+
+    /// The writes so far, oldest first.
+    pub fn ops(&self) -> &[WalOp] {
+        &self.ops
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: KvStore> KvStore for RecordingStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        self.ops.push(WalOp::CreateGroup {
+            group_name: group_name.to_string(),
+        });
+        self.inner.create_group(group_name)
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        self.ops.push(WalOp::Append {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+            data: data.clone(),
+        });
+        self.inner.put(group_name, data_name, data)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        self.ops.push(WalOp::RemoveEntry {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+        });
+        self.inner.remove_data_entry(group_name, data_name)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        self.ops.push(WalOp::RemoveGroup {
+            group_name: group_name.to_string(),
+        });
+        self.inner.remove_group(group_name)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
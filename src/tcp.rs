@@ -0,0 +1,143 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::base64;
+use crate::db::{get_data, insert_data, list_data_names, list_groups, remove_data_entry};
+use crate::error::{Error, ErrorKind};
+use crate::persist::PersistentDb;
+
+// How often blocked accepts and reads look at the shutdown flag.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Serves db over a newline delimited text protocol on listener until
+/// shutdown is set, then waits for every connection to finish its current
+/// command. Each connection gets its own thread.
+///
+/// ```text
+/// GET <group> <data_name>          +VALUE <base64 data>
+/// SET <group> <data_name> <base64> +OK
+/// DEL <group> <data_name>          +OK
+/// GROUPS                           +VALUE <base64 of the names, one per line>
+/// DATA_NAMES <group>               +VALUE <base64 of the names, one per line>
+/// SAVE                             +OK
+/// ```
+///
+/// Failures answer with -ERR <kind> <message> and the connection stays open.
+/// Names are single words, there is no quoting.
+pub fn serve_tcp(
+    db: Arc<Mutex<PersistentDb>>,
+    listener: TcpListener,
+    shutdown: Arc<AtomicBool>,
+) -> Result<(), Error> {
+    let accept_error = |why: io::Error| {
+        Error::new(
+            ErrorKind::Io,
+            format!("accepting a connection failed: {}", why),
+        )
+        .with_source(why)
+    };
+    listener.set_nonblocking(true).map_err(accept_error)?;
+    let mut connections = Vec::new();
+    while !shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let db = Arc::clone(&db);
+                let shutdown = Arc::clone(&shutdown);
+                // A client that goes away only loses its own connection.
+                connections.push(thread::spawn(move || {
+                    let _ = handle_connection(&db, stream, &shutdown);
+                }));
+            }
+            Err(why) if why.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(why) => return Err(accept_error(why)),
+        }
+        connections.retain(|connection| !connection.is_finished());
+    }
+    for connection in connections {
+        let _ = connection.join();
+    }
+    Ok(())
+}
+
+fn handle_connection(
+    db: &Mutex<PersistentDb>,
+    stream: TcpStream,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+    let mut line = Vec::new();
+    while !shutdown.load(Ordering::Relaxed) {
+        // A timed out read keeps what it got in line, the rest comes later.
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(why)
+                if matches!(
+                    why.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(why) => return Err(why),
+        }
+        let response = match std::str::from_utf8(&line) {
+            Ok(command) => {
+                let mut db = db.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                run_command(&mut db, command.trim_end())
+            }
+            Err(_) => "-ERR BadRequest command is not UTF-8".to_string(),
+        };
+        writeln!(writer, "{}", response)?;
+        line.clear();
+    }
+    Ok(())
+}
+
+fn run_command(db: &mut PersistentDb, command: &str) -> String {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let result = match words.as_slice() {
+        ["GET", group_name, data_name] => {
+            get_data(db, group_name, data_name).map(|data| Some(base64::encode(data.as_bytes())))
+        }
+        ["SET", group_name, data_name, encoded] => match decode_text(encoded) {
+            Some(data) => insert_data(db, group_name, data_name, data, true).map(|_| None),
+            None => return "-ERR BadRequest data is not base64 encoded UTF-8".to_string(),
+        },
+        // Looked up first so a miss has the same error as a GET.
+        ["DEL", group_name, data_name] => {
+            get_data(db, group_name, data_name).map(|_| ()).map(|()| {
+                remove_data_entry(db, group_name, data_name);
+                None
+            })
+        }
+        ["GROUPS"] => Ok(Some(encode_names(&list_groups(db)))),
+        ["DATA_NAMES", group_name] => {
+            list_data_names(db, group_name).map(|data_names| Some(encode_names(&data_names)))
+        }
+        ["SAVE"] => db.save().map(|()| None),
+        _ => return format!("-ERR BadRequest unknown command '{}'", command),
+    };
+    match result {
+        Ok(Some(encoded)) => format!("+VALUE {}", encoded),
+        Ok(None) => "+OK".to_string(),
+        // Messages are one line already, this keeps a stray newline from
+        // splitting a response.
+        Err(why) => format!("-ERR {:?} {}", why.kind(), why.message().replace('\n', " ")),
+    }
+}
+
+fn decode_text(encoded: &str) -> Option<String> {
+    String::from_utf8(base64::decode(encoded)?).ok()
+}
+
+fn encode_names(names: &[String]) -> String {
+    base64::encode(names.join("\n").as_bytes())
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::db::{get_data, insert_data, read_as, remove_data_entry};
+use crate::error::{Error, ErrorKind};
+use crate::history::{is_keep_key, keep_key};
+use crate::Db;
+
+/// When an entry was first and last written, in seconds since the Unix epoch.
+/// Both are 0 for entries last written before timestamps existed, including
+/// everything loaded from files that have none.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EntryMeta {
+    pub created: u64,
+    pub modified: u64,
+}
+
+const META_KINDS: [&str; 6] = [
+    "created",
+    "modified",
+    "expires",
+    "compressed",
+    "history",
+    "deleted",
+];
+
+// Kept in the name group like the schema version, so they are saved as
+// metadata lines. The group name's length comes first so a ':' in either name
+// can't make two entries share a key.
+fn meta_key(kind: &str, group_name: &str, data_name: &str) -> String {
+    format!("{}:{}:{}:{}", kind, group_name.len(), group_name, data_name)
+}
+
+pub(crate) fn entry_meta_value<'a>(
+    db: &'a Db,
+    kind: &str,
+    group_name: &str,
+    data_name: &str,
+) -> Option<&'a String> {
+    db.get("name")?.get(&meta_key(kind, group_name, data_name))
+}
+
+// None removes it.
+pub(crate) fn set_entry_meta_value(
+    db: &mut Db,
+    kind: &str,
+    group_name: &str,
+    data_name: &str,
+    value: Option<String>,
+) {
+    let key = meta_key(kind, group_name, data_name);
+    match value {
+        Some(value) => {
+            db.entry("name".to_string()).or_default().insert(key, value);
+        }
+        None => {
+            if let Some(name_group) = db.get_mut("name") {
+                name_group.remove(&key);
+            }
+        }
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+// Every write of an entry goes through here, it records the time as modified,
+// and as created when the entry had no created time yet. An expiry that
+// already passed belonged to the entry this write replaces, and the data
+// written is neither compressed nor deleted. Entries of the name group are
+// metadata themselves and get no times.
+pub(crate) fn record_write(db: &mut Db, group_name: &str, data_name: &str) {
+    if group_name == "name" {
+        return;
+    }
+    if is_expired(db, group_name, data_name) {
+        set_entry_meta_value(db, "expires", group_name, data_name, None);
+    }
+    set_entry_meta_value(db, "compressed", group_name, data_name, None);
+    set_entry_meta_value(db, "deleted", group_name, data_name, None);
+    let now = now().to_string();
+    let name_group = db.entry("name".to_string()).or_default();
+    name_group
+        .entry(meta_key("created", group_name, data_name))
+        .or_insert_with(|| now.clone());
+    name_group.insert(meta_key("modified", group_name, data_name), now);
+}
+
+/// The same as insert_data, which records the times itself now. Kept for
+/// callers written before it did.
+pub fn insert_data_timed(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    data: String,
+    create_group: bool,
+) -> Result<Option<String>, Error> {
+    insert_data(db, group_name, data_name, data, create_group)
+}
+
+/// The entry's times, with the errors of get_data when it doesn't exist.
+pub fn entry_metadata(db: &Db, group_name: &str, data_name: &str) -> Result<EntryMeta, Error> {
+    get_data(db, group_name, data_name)?;
+    let time = |kind: &str| {
+        db.get("name")
+            .and_then(|inner| inner.get(&meta_key(kind, group_name, data_name)))
+            .and_then(|time| time.parse().ok())
+            .unwrap_or(0)
+    };
+    Ok(EntryMeta {
+        created: time("created"),
+        modified: time("modified"),
+    })
+}
+
+// Called when entries go away so a later entry of the same name doesn't
+// inherit their created time. data_name None drops the whole group's.
+pub(crate) fn forget_entry_metadata(db: &mut Db, group_name: &str, data_name: Option<&str>) {
+    let Some(name_group) = db.get_mut("name") else {
+        return;
+    };
+    match data_name {
+        Some(data_name) => {
+            for kind in META_KINDS {
+                name_group.remove(&meta_key(kind, group_name, data_name));
+            }
+        }
+        None => {
+            let prefixes = META_KINDS.map(|kind| meta_key(kind, group_name, ""));
+            name_group.retain(|key, _| !prefixes.iter().any(|prefix| key.starts_with(prefix)));
+        }
+    }
+}
+
+// Called when the group itself goes away, so a group created later under the
+// same name starts without its entries' metadata or its history setting.
+pub(crate) fn forget_group_metadata(db: &mut Db, group_name: &str) {
+    forget_entry_metadata(db, group_name, None);
+    if let Some(name_group) = db.get_mut("name") {
+        name_group.remove(&keep_key(group_name));
+    }
+}
+
+// Called when an entry is renamed within its group, its times, expiry and
+// marks go with it and whatever the entry it replaced had is dropped.
+pub(crate) fn move_entry_metadata(db: &mut Db, group_name: &str, from: &str, to: &str) {
+    forget_entry_metadata(db, group_name, Some(to));
+    let Some(name_group) = db.get_mut("name") else {
+        return;
+    };
+    for kind in META_KINDS {
+        if let Some(value) = name_group.remove(&meta_key(kind, group_name, from)) {
+            name_group.insert(meta_key(kind, group_name, to), value);
+        }
+    }
+}
+
+// Called when a group is copied to dst, or moved there when move_group is set.
+// Whatever dst had is dropped first.
+pub(crate) fn copy_group_metadata(db: &mut Db, src: &str, dst: &str, move_group: bool) {
+    if src == dst {
+        return;
+    }
+    forget_entry_metadata(db, dst, None);
+    let Some(name_group) = db.get_mut("name") else {
+        return;
+    };
+    let mut copied = Vec::new();
+    for (key, value) in name_group.iter() {
+        let Some((kind, rest)) = key.split_once(':') else {
+            continue;
+        };
+        if !META_KINDS.contains(&kind) {
+            continue;
+        }
+        if let Some((group_name, data_name)) = split_meta_key(rest) {
+            if group_name == src {
+                copied.push((key.clone(), meta_key(kind, dst, data_name), value.clone()));
+            }
+        }
+    }
+    for (key, copy_key, value) in copied {
+        if move_group {
+            name_group.remove(&key);
+        }
+        name_group.insert(copy_key, value);
+    }
+    let keep = match move_group {
+        true => name_group.remove(&keep_key(src)),
+        false => name_group.get(&keep_key(src)).cloned(),
+    };
+    match keep {
+        Some(keep) => name_group.insert(keep_key(dst), keep),
+        None => name_group.remove(&keep_key(dst)),
+    };
+}
+
+// Called when another database's copy of an entry replaces this one's, as in
+// merge_from. When it was written, how it is stored and when it expires come
+// with it, a created time this one already had stays.
+pub(crate) fn copy_entry_metadata(db: &mut Db, other: &Db, group_name: &str, data_name: &str) {
+    for kind in ["modified", "expires", "compressed"] {
+        let value = entry_meta_value(other, kind, group_name, data_name).cloned();
+        set_entry_meta_value(db, kind, group_name, data_name, value);
+    }
+    if let Some(created) = entry_meta_value(other, "created", group_name, data_name).cloned() {
+        db.entry("name".to_string())
+            .or_default()
+            .entry(meta_key("created", group_name, data_name))
+            .or_insert(created);
+    }
+    set_entry_meta_value(db, "deleted", group_name, data_name, None);
+}
+
+// Called when every group but the name group goes away.
+pub(crate) fn forget_all_entry_metadata(db: &mut Db) {
+    if let Some(name_group) = db.get_mut("name") {
+        name_group.retain(|key, _| {
+            key.split_once(':')
+                .is_none_or(|(kind, _)| !META_KINDS.contains(&kind))
+                && !is_keep_key(key)
+        });
+    }
+}
+
+/// insert_data that also makes the entry expire once ttl has passed.
+/// Writing the entry again without a ttl keeps the expiry it had.
+pub fn insert_with_ttl(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    data: String,
+    ttl: Duration,
+    create_group: bool,
+) -> Result<Option<String>, Error> {
+    let previous = insert_data(db, group_name, data_name, data, create_group)?;
+    let expires = now().saturating_add(ttl.as_secs());
+    db.entry("name".to_string()).or_default().insert(
+        meta_key("expires", group_name, data_name),
+        expires.to_string(),
+    );
+    Ok(previous)
+}
+
+// None for entries that never expire.
+pub(crate) fn expires_at(db: &Db, group_name: &str, data_name: &str) -> Option<u64> {
+    db.get("name")?
+        .get(&meta_key("expires", group_name, data_name))?
+        .parse()
+        .ok()
+}
+
+/// read_as that also removes the entry when it has expired. Every read treats
+/// an expired entry as DataNotFound, this one takes it out of the Db too.
+pub fn read_unexpired<T: FromStr>(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+) -> Result<T, Error>
+where
+    T::Err: fmt::Display,
+{
+    if is_expired(db, group_name, data_name) {
+        remove_data_entry(db, group_name, data_name);
+        return Err(expired_error(group_name, data_name));
+    }
+    read_as(db, group_name, data_name)
+}
+
+pub(crate) fn is_expired(db: &Db, group_name: &str, data_name: &str) -> bool {
+    expires_at(db, group_name, data_name).is_some_and(|expires| expires <= now())
+}
+
+pub(crate) fn expired_error(group_name: &str, data_name: &str) -> Error {
+    Error::new(
+        ErrorKind::DataNotFound,
+        format!("'{}' in group '{}' has expired", data_name, group_name),
+    )
+}
+
+/// Removes every expired entry, returning how many there were. Expiry times
+/// are saved with the database, so entries stay expired across a reload.
+pub fn purge_expired(db: &mut Db) -> usize {
+    let now = now();
+    let mut expired: Vec<(String, String)> = Vec::new();
+    if let Some(name_group) = db.get("name") {
+        for (key, expires) in name_group {
+            let Some(rest) = key.strip_prefix("expires:") else {
+                continue;
+            };
+            if !expires.parse::<u64>().is_ok_and(|expires| expires <= now) {
+                continue;
+            }
+            if let Some((group_name, data_name)) = split_meta_key(rest) {
+                expired.push((group_name.to_string(), data_name.to_string()));
+            }
+        }
+    }
+    let mut purged = 0;
+    for (group_name, data_name) in expired {
+        match remove_data_entry(db, &group_name, &data_name) {
+            Some(_) => purged += 1,
+            // The entry went away some other way, only its times are left.
+            None => forget_entry_metadata(db, &group_name, Some(&data_name)),
+        }
+    }
+    purged
+}
+
+// Whether key, in the name group, is metadata about an entry rather than
+// something the application stored.
+pub(crate) fn is_entry_meta_key(key: &str) -> bool {
+    key.split_once(':')
+        .is_some_and(|(kind, rest)| META_KINDS.contains(&kind) && split_meta_key(rest).is_some())
+}
+
+// The group and data_name out of what follows "<kind>:" in a meta key.
+pub(crate) fn split_meta_key(rest: &str) -> Option<(&str, &str)> {
+    let (len, rest) = rest.split_once(':')?;
+    let len: usize = len.parse().ok()?;
+    let group_name = rest.get(..len)?;
+    let data_name = rest.get(len..)?.strip_prefix(':')?;
+    Some((group_name, data_name))
+}
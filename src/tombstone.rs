@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use crate::db::remove_data_entry;
+use crate::timestamps::{entry_meta_value, now, set_entry_meta_value, split_meta_key};
+use crate::Db;
+
+/// remove_data_entry that leaves a tombstone with the time behind, so
+/// merge_from won't bring the entry back from a database that still has an
+/// older copy. Tombstones are saved with the other entry metadata, see
+/// purge_tombstones for getting rid of them.
+pub fn remove_data_entry_soft(db: &mut Db, group_name: &str, data_name: &str) -> Option<String> {
+    let removed = remove_data_entry(db, group_name, data_name)?;
+    set_entry_meta_value(
+        db,
+        "deleted",
+        group_name,
+        data_name,
+        Some(now().to_string()),
+    );
+    Some(removed)
+}
+
+/// Removes the tombstones at least older_than old, returning how many there
+/// were.
+pub fn purge_tombstones(db: &mut Db, older_than: Duration) -> usize {
+    let cutoff = now().saturating_sub(older_than.as_secs());
+    let Some(name_group) = db.get_mut("name") else {
+        return 0;
+    };
+    let before = name_group.len();
+    name_group.retain(|key, deleted| {
+        !key.starts_with("deleted:") || deleted.parse::<u64>().is_ok_and(|deleted| deleted > cutoff)
+    });
+    before - name_group.len()
+}
+
+// When the entry was soft removed, None when it has no tombstone.
+pub(crate) fn tombstone_time(db: &Db, group_name: &str, data_name: &str) -> Option<u64> {
+    entry_meta_value(db, "deleted", group_name, data_name)?
+        .parse()
+        .ok()
+}
+
+// When the entry was last written, 0 when that was before times were kept.
+pub(crate) fn modified_time(db: &Db, group_name: &str, data_name: &str) -> u64 {
+    entry_meta_value(db, "modified", group_name, data_name)
+        .and_then(|modified| modified.parse().ok())
+        .unwrap_or(0)
+}
+
+// Every tombstone in db as (group_name, data_name, time).
+pub(crate) fn tombstones(db: &Db) -> Vec<(String, String, u64)> {
+    let Some(name_group) = db.get("name") else {
+        return Vec::new();
+    };
+    let mut tombstones = Vec::new();
+    for (key, deleted) in name_group {
+        let Some((group_name, data_name)) = key.strip_prefix("deleted:").and_then(split_meta_key)
+        else {
+            continue;
+        };
+        if let Ok(deleted) = deleted.parse() {
+            tombstones.push((group_name.to_string(), data_name.to_string(), deleted));
+        }
+    }
+    tombstones.sort();
+    tombstones
+}
+
+// A tombstone at deleted in another database, applied to db: it removes an
+// entry written before it and is kept when db has no entry. Returns whether
+// it removed one.
+pub(crate) fn apply_tombstone(
+    db: &mut Db,
+    group_name: &str,
+    data_name: &str,
+    deleted: u64,
+) -> bool {
+    let exists = db
+        .get(group_name)
+        .is_some_and(|group| group.contains_key(data_name));
+    if exists && modified_time(db, group_name, data_name) >= deleted {
+        return false;
+    }
+    let removed = exists && remove_data_entry(db, group_name, data_name).is_some();
+    let deleted = deleted.max(tombstone_time(db, group_name, data_name).unwrap_or(0));
+    set_entry_meta_value(
+        db,
+        "deleted",
+        group_name,
+        data_name,
+        Some(deleted.to_string()),
+    );
+    removed
+}
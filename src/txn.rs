@@ -0,0 +1,189 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::db::{
+    contains_group, create_group, get_data, insert_data, list_data_names, list_groups,
+    remove_data_entry, remove_group,
+};
+use crate::error::{Error, ErrorKind};
+use crate::store::KvStore;
+use crate::Db;
+
+/// Changes staged by a transaction, read through on top of the Db they will
+/// be applied to. Used through KvStore, reads see the transaction's own
+/// writes.
+pub struct Txn<'a> {
+    db: &'a Db,
+    groups: HashMap<String, GroupOverlay>,
+}
+
+struct GroupOverlay {
+    exists: bool,
+    // The group was removed, so none of the Db's entries in it are left.
+    removed: bool,
+    // None for entries the transaction removed.
+    entries: HashMap<String, Option<String>>,
+}
+
+/// Runs f against a Txn and applies everything it staged to db when it
+/// returns Ok. On Err db is left as it was. The Txn borrows db, so
+/// transactions can't be nested.
+pub fn transaction<F, R>(db: &mut Db, f: F) -> Result<R, Error>
+where
+    F: FnOnce(&mut Txn) -> Result<R, Error>,
+{
+    let mut txn = Txn {
+        db,
+        groups: HashMap::new(),
+    };
+    let result = f(&mut txn)?;
+    let groups = txn.groups;
+    for (group_name, overlay) in groups {
+        if overlay.removed {
+            remove_group(db, &group_name);
+        }
+        if overlay.exists {
+            create_group(db, &group_name);
+        }
+        for (data_name, data) in overlay.entries {
+            match data {
+                // Only the name group can be refused and put never stages it.
+                Some(data) => {
+                    insert_data(db, &group_name, &data_name, data, true)
+                        .expect("the name group was staged");
+                }
+                None => {
+                    remove_data_entry(db, &group_name, &data_name);
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+impl Txn<'_> {
+    fn overlay(&mut self, group_name: &str) -> &mut GroupOverlay {
+        let db = self.db;
+        self.groups
+            .entry(group_name.to_string())
+            .or_insert_with(|| GroupOverlay {
+                exists: contains_group(db, group_name),
+                removed: false,
+                entries: HashMap::new(),
+            })
+    }
+
+    fn group_exists(&self, group_name: &str) -> bool {
+        match self.groups.get(group_name) {
+            Some(overlay) => overlay.exists,
+            None => contains_group(self.db, group_name),
+        }
+    }
+
+    fn check_group(&self, group_name: &str) -> Result<(), Error> {
+        if self.group_exists(group_name) {
+            return Ok(());
+        }
+        Err(Error::new(
+            ErrorKind::GroupNotFound,
+            format!("group '{}' does not exist", group_name),
+        ))
+    }
+}
+
+impl KvStore for Txn<'_> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        if group_name == "name" || self.group_exists(group_name) {
+            return false;
+        }
+        self.overlay(group_name).exists = true;
+        true
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        if group_name == "name" {
+            self.check_group(group_name)?;
+        }
+        let previous = self.get_raw(group_name, data_name).ok();
+        let overlay = self.overlay(group_name);
+        overlay.exists = true;
+        overlay.entries.insert(data_name.to_string(), Some(data));
+        Ok(previous)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.check_group(group_name)?;
+        let Some(overlay) = self.groups.get(group_name) else {
+            return get_data(self.db, group_name, data_name).map(Cow::into_owned);
+        };
+        let staged = match overlay.entries.get(data_name) {
+            Some(staged) => staged.clone(),
+            None if overlay.removed => None,
+            None => self
+                .db
+                .get(group_name)
+                .and_then(|group| group.get(data_name))
+                .cloned(),
+        };
+        staged.ok_or_else(|| {
+            Error::new(
+                ErrorKind::DataNotFound,
+                format!("'{}' does not exist in group '{}'", data_name, group_name),
+            )
+        })
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let previous = self.get_raw(group_name, data_name).ok()?;
+        self.overlay(group_name)
+            .entries
+            .insert(data_name.to_string(), None);
+        Some(previous)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let removed = self.list_data_names(group_name).ok()?.len();
+        let overlay = self.overlay(group_name);
+        overlay.exists = false;
+        overlay.removed = true;
+        overlay.entries.clear();
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        let mut group_names: BTreeSet<String> = list_groups(self.db).into_iter().collect();
+        for (group_name, overlay) in &self.groups {
+            if overlay.exists {
+                group_names.insert(group_name.clone());
+            } else {
+                group_names.remove(group_name);
+            }
+        }
+        group_names.into_iter().collect()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.check_group(group_name)?;
+        let overlay = self.groups.get(group_name);
+        let mut data_names: BTreeSet<String> = match overlay {
+            Some(overlay) if overlay.removed => BTreeSet::new(),
+            _ => list_data_names(self.db, group_name)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        };
+        for (data_name, data) in overlay.iter().flat_map(|overlay| &overlay.entries) {
+            if data.is_some() {
+                data_names.insert(data_name.clone());
+            } else {
+                data_names.remove(data_name);
+            }
+        }
+        Ok(data_names.into_iter().collect())
+    }
+}
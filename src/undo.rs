@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use crate::config::DbConfig;
+use crate::error::Error;
+use crate::store::KvStore;
+
+/// A write made through an UndoStore, with what it takes to reverse it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpRecord {
+    CreateGroup {
+        group_name: String,
+    },
+    Put {
+        group_name: String,
+        data_name: String,
+        /// None when the entry was new.
+        previous: Option<String>,
+        /// The put created the group.
+        created_group: bool,
+    },
+    RemoveEntry {
+        group_name: String,
+        data_name: String,
+        previous: String,
+    },
+    RemoveGroup {
+        group_name: String,
+        entries: Vec<(String, String)>,
+    },
+}
+
+/// Wraps a store and remembers its last writes so they can be undone, newest
+/// first. Only writes that changed something are remembered, and only as
+/// many as the DbConfig's undo_log_len. The log only lives in memory.
+pub struct UndoStore<S> {
+    inner: S,
+    log: VecDeque<OpRecord>,
+    log_len: usize,
+}
+
+impl<S: KvStore> UndoStore<S> {
+    pub fn new(inner: S, config: &DbConfig) -> UndoStore<S> {
+        UndoStore {
+            inner,
+            log: VecDeque::new(),
+            log_len: config.undo_log_len,
+        }
+    }
+
+    fn record(&mut self, op: OpRecord) {
+        if self.log_len == 0 {
+            return;
+        }
+        if self.log.len() == self.log_len {
+            self.log.pop_front();
+        }
+        self.log.push_back(op);
+    }
+
+    /// Up to n of the writes undo would reverse, newest first.
+    pub fn recent_ops(&self, n: usize) -> Vec<&OpRecord> {
+        self.log.iter().rev().take(n).collect()
+    }
+
+    /// Reverses the newest write and returns it, None when there is nothing
+    /// left to undo. The write stays in the log if reversing it fails.
+    pub fn undo(&mut self) -> Result<Option<OpRecord>, Error> {
+        let Some(op) = self.log.pop_back() else {
+            return Ok(None);
+        };
+        if let Err(why) = self.reverse(&op) {
+            self.log.push_back(op);
+            return Err(why);
+        }
+        Ok(Some(op))
+    }
+
+    fn reverse(&mut self, op: &OpRecord) -> Result<(), Error> {
+        match op {
+            OpRecord::CreateGroup { group_name } => {
+                self.inner.remove_group(group_name);
+            }
+            OpRecord::Put {
+                group_name,
+                data_name,
+                previous,
+                created_group,
+            } => {
+                match previous {
+                    Some(previous) => {
+                        self.inner.put(group_name, data_name, previous.clone())?;
+                    }
+                    None => {
+                        self.inner.remove_data_entry(group_name, data_name);
+                    }
+                }
+                if *created_group {
+                    self.inner.remove_group(group_name);
+                }
+            }
+            OpRecord::RemoveEntry {
+                group_name,
+                data_name,
+                previous,
+            } => {
+                self.inner.put(group_name, data_name, previous.clone())?;
+            }
+            OpRecord::RemoveGroup {
+                group_name,
+                entries,
+            } => {
+                self.inner.create_group(group_name);
+                for (data_name, data) in entries {
+                    self.inner.put(group_name, data_name, data.clone())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: KvStore> KvStore for UndoStore<S> {
+    fn create_group(&mut self, group_name: &str) -> bool {
+        let created = self.inner.create_group(group_name);
+        if created {
+            self.record(OpRecord::CreateGroup {
+                group_name: group_name.to_string(),
+            });
+        }
+        created
+    }
+
+    fn put(
+        &mut self,
+        group_name: &str,
+        data_name: &str,
+        data: String,
+    ) -> Result<Option<String>, Error> {
+        let created_group = self.inner.list_data_names(group_name).is_err();
+        let previous = self.inner.put(group_name, data_name, data)?;
+        self.record(OpRecord::Put {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+            previous: previous.clone(),
+            created_group,
+        });
+        Ok(previous)
+    }
+
+    fn get_raw(&self, group_name: &str, data_name: &str) -> Result<String, Error> {
+        self.inner.get_raw(group_name, data_name)
+    }
+
+    fn remove_data_entry(&mut self, group_name: &str, data_name: &str) -> Option<String> {
+        let previous = self.inner.remove_data_entry(group_name, data_name)?;
+        self.record(OpRecord::RemoveEntry {
+            group_name: group_name.to_string(),
+            data_name: data_name.to_string(),
+            previous: previous.clone(),
+        });
+        Some(previous)
+    }
+
+    fn remove_group(&mut self, group_name: &str) -> Option<usize> {
+        let entries: Vec<(String, String)> = self
+            .inner
+            .list_data_names(group_name)
+            .ok()?
+            .into_iter()
+            .filter_map(|data_name| {
+                let data = self.inner.get_raw(group_name, &data_name).ok()?;
+                Some((data_name, data))
+            })
+            .collect();
+        let removed = self.inner.remove_group(group_name)?;
+        self.record(OpRecord::RemoveGroup {
+            group_name: group_name.to_string(),
+            entries,
+        });
+        Some(removed)
+    }
+
+    fn list_groups(&self) -> Vec<String> {
+        self.inner.list_groups()
+    }
+
+    fn list_data_names(&self, group_name: &str) -> Result<Vec<String>, Error> {
+        self.inner.list_data_names(group_name)
+    }
+}
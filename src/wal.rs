@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::db::{create_group, insert_data, remove_data_entry, remove_group};
+use crate::error::{Error, ErrorKind};
+use crate::file::{atomic_save, load_from_file};
+use crate::format::crc32;
+use crate::Db;
+
+/// Write-ahead log, every change is appended to the log before it is applied so
+/// a database can be restored from its last snapshot plus the log.
+///
+/// Record: payload length (u32 LE), CRC32 of the payload (u32 LE), payload.
+/// Payload: op byte, then each string as a u32 LE length followed by its bytes.
+#[derive(Debug, PartialEq)]
+pub enum WalOp {
+    CreateGroup { group_name: String },
+    Append { group_name: String, data_name: String, data: String },
+    RemoveEntry { group_name: String, data_name: String },
+    RemoveGroup { group_name: String },
+}
+
+impl WalOp {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let (op, fields): (u8, Vec<&String>) = match self {
+            WalOp::CreateGroup { group_name } => (0, vec![group_name]),
+            WalOp::Append { group_name, data_name, data } => (1, vec![group_name, data_name, data]),
+            WalOp::RemoveEntry { group_name, data_name } => (2, vec![group_name, data_name]),
+            WalOp::RemoveGroup { group_name } => (3, vec![group_name]),
+        };
+        let mut payload = vec![op];
+        for field in fields {
+            payload.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            payload.extend_from_slice(field.as_bytes());
+        }
+        payload
+    }
+
+    pub(crate) fn decode(payload: &[u8]) -> Option<WalOp> {
+        let (&op, mut rest) = payload.split_first()?;
+        let mut next_field = || -> Option<String> {
+            let (len, tail) = rest.split_first_chunk::<4>()?;
+            let len = u32::from_le_bytes(*len) as usize;
+            let field = String::from_utf8(tail.get(..len)?.to_vec()).ok()?;
+            rest = &tail[len..];
+            Some(field)
+        };
+        let op = match op {
+            0 => WalOp::CreateGroup { group_name: next_field()? },
+            1 => WalOp::Append {
+                group_name: next_field()?,
+                data_name: next_field()?,
+                data: next_field()?,
+            },
+            2 => WalOp::RemoveEntry { group_name: next_field()?, data_name: next_field()? },
+            3 => WalOp::RemoveGroup { group_name: next_field()? },
+            _ => return None,
+        };
+        rest.is_empty().then_some(op)
+    }
+
+    /// Applies the op to db without logging it, through the same functions a
+    /// direct write would use so the entry metadata stays right. Conflict for
+    /// ops on the name group, which isn't a real group.
+    pub fn apply(&self, db: &mut Db) -> Result<(), Error> {
+        self.check()?;
+        match self {
+            WalOp::CreateGroup { group_name } => {
+                create_group(db, group_name);
+            }
+            WalOp::Append { group_name, data_name, data } => {
+                insert_data(db, group_name, data_name, data.clone(), true)?;
+            }
+            WalOp::RemoveEntry { group_name, data_name } => {
+                remove_data_entry(db, group_name, data_name);
+            }
+            WalOp::RemoveGroup { group_name } => {
+                remove_group(db, group_name);
+            }
+        }
+        Ok(())
+    }
+
+    // Everything apply can refuse, so a refused op is never logged.
+    fn check(&self) -> Result<(), Error> {
+        let group_name = match self {
+            WalOp::CreateGroup { group_name }
+            | WalOp::Append { group_name, .. }
+            | WalOp::RemoveEntry { group_name, .. }
+            | WalOp::RemoveGroup { group_name } => group_name,
+        };
+        if group_name == "name" {
+            return Err(Error::new(
+                ErrorKind::Conflict,
+                "'name' is reserved and cannot be a group".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// An open log, see open_with_wal.
+pub struct Wal {
+    file: File,
+    path: PathBuf,
+}
+
+/// Loads the snapshot and replays the log over it. A torn or corrupt record
+/// ends the replay, it and anything after it are cut from the log. An intact
+/// record apply refuses fails the open, the log is left as it was.
+pub fn open_with_wal(
+    snapshot_path: impl AsRef<Path>,
+    wal_path: impl AsRef<Path>,
+) -> Result<(Db, Wal), Error> {
+    let mut db = load_from_file(snapshot_path)?;
+    let path = wal_path.as_ref().to_path_buf();
+    let mut file = File::options()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+        .map_err(|why| Error::io(&path, why))?;
+    let mut log = Vec::new();
+    file.read_to_end(&mut log).map_err(|why| Error::io(&path, why))?;
+
+    let mut valid_len = 0;
+    let mut rest = log.as_slice();
+    while let Some((header, tail)) = rest.split_first_chunk::<8>() {
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let Some(payload) = tail.get(..len).filter(|payload| crc32(payload) == crc) else {
+            break;
+        };
+        let Some(op) = WalOp::decode(payload) else {
+            break;
+        };
+        op.apply(&mut db)
+            .map_err(|why| Error::new(why.kind, format!("{}: {}", path.display(), why.message)))?;
+        valid_len += 8 + len;
+        rest = &tail[len..];
+    }
+    if valid_len < log.len() {
+        file.set_len(valid_len as u64).map_err(|why| Error::io(&path, why))?;
+    }
+    Ok((db, Wal { file, path }))
+}
+
+impl Wal {
+    /// The op is synced to the log before it is applied to db. An op apply
+    /// would refuse is refused before it reaches the log.
+    pub fn log(&mut self, db: &mut Db, op: WalOp) -> Result<(), Error> {
+        op.check()?;
+        let payload = op.encode();
+        let mut record = Vec::with_capacity(8 + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32(&payload).to_le_bytes());
+        record.extend_from_slice(&payload);
+        self.file
+            .write_all(&record)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|why| Error::io(&self.path, why))?;
+        op.apply(db)
+    }
+
+    /// Folds the log into a fresh snapshot and empties it.
+    pub fn checkpoint(&mut self, db: &Db, snapshot_path: impl AsRef<Path>) -> Result<(), Error> {
+        atomic_save(db, snapshot_path)?;
+        self.file
+            .set_len(0)
+            .and_then(|_| self.file.sync_all())
+            .map_err(|why| Error::io(&self.path, why))
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use plain_text_db_rust::{
+    get_data, insert_data, load_from_file, start_autosave, ErrorKind, PersistentDb,
+};
+
+#[test]
+fn changes_are_saved_in_the_background() {
+    let dir = common::temp_dir("changes_are_saved_in_the_background");
+    let path = dir.join("db.pdbr");
+    let db = Arc::new(Mutex::new(PersistentDb::new(common::sample_db(), &path)));
+    let autosave = start_autosave(db.clone(), Duration::from_millis(10));
+    insert_data(
+        &mut db.lock().unwrap(),
+        "users",
+        "carol",
+        "3".to_string(),
+        true,
+    )
+    .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !load_from_file(&path).is_ok_and(|saved| get_data(&saved, "users", "carol").is_ok()) {
+        assert!(Instant::now() < deadline, "autosave never wrote the change");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    autosave.stop().unwrap();
+}
+
+#[test]
+fn flush_now_and_stop_save_only_changes() {
+    let dir = common::temp_dir("flush_now_and_stop_save_only_changes");
+    let path = dir.join("db.pdbr");
+    let db = Arc::new(Mutex::new(PersistentDb::new(common::sample_db(), &path)));
+    let autosave = start_autosave(db.clone(), Duration::from_secs(3600));
+    assert!(autosave.flush_now().unwrap());
+    assert!(!autosave.flush_now().unwrap());
+    insert_data(
+        &mut db.lock().unwrap(),
+        "users",
+        "dave",
+        "4".to_string(),
+        true,
+    )
+    .unwrap();
+    autosave.stop().unwrap();
+    let saved = load_from_file(&path).unwrap();
+    assert_eq!(get_data(&saved, "users", "dave").unwrap(), "4");
+}
+
+#[test]
+fn dropping_the_handle_ends_the_thread_without_saving() {
+    let dir = common::temp_dir("dropping_the_handle_ends_the_thread_without_saving");
+    let path = dir.join("db.pdbr");
+    let db = Arc::new(Mutex::new(PersistentDb::new(common::sample_db(), &path)));
+    drop(start_autosave(db.clone(), Duration::from_secs(3600)));
+    assert_eq!(
+        load_from_file(&path).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    // The PersistentDb's own drop still saves.
+    drop(db);
+    assert_eq!(load_from_file(&path).unwrap(), common::sample_db());
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use std::sync::Arc;
+
+use plain_text_db_rust::{cached, insert_data, remove_data_entry, ErrorKind};
+
+#[test]
+fn cached_reads_see_every_write() {
+    let mut db = common::sample_db();
+    let mut limit = cached::<u32>("settings", "limit");
+    insert_data(&mut db, "settings", "limit", "10".to_string(), false).unwrap();
+    let first = limit.get(&db).unwrap();
+    assert_eq!(*first, 10);
+    let again = limit.get(&db).unwrap();
+    assert!(Arc::ptr_eq(&first, &again));
+    insert_data(&mut db, "settings", "limit", "20".to_string(), false).unwrap();
+    assert_eq!(*limit.get(&db).unwrap(), 20);
+    // A write straight to the map, past every db function, isn't missed either.
+    db.get_mut("settings")
+        .unwrap()
+        .insert("limit".to_string(), "30".to_string());
+    assert_eq!(*limit.get(&db).unwrap(), 30);
+    remove_data_entry(&mut db, "settings", "limit");
+    let why = limit.get(&db).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::DataNotFound);
+}
+
+#[test]
+fn parse_errors_arent_cached() {
+    let mut db = common::sample_db();
+    let mut limit = cached::<u32>("settings", "limit");
+    insert_data(&mut db, "settings", "limit", "lots".to_string(), false).unwrap();
+    assert_eq!(limit.get(&db).unwrap_err().kind(), ErrorKind::Parse);
+    insert_data(&mut db, "settings", "limit", "5".to_string(), false).unwrap();
+    assert_eq!(*limit.get(&db).unwrap(), 5);
+}
@@ -0,0 +1,36 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use plain_text_db_rust::{insert_data, Db};
+
+/// A fresh directory for one test, removed first if an earlier run left it.
+pub fn temp_dir(test_name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "plain-text-db-{}-{}",
+        std::process::id(),
+        test_name
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A database called test with two small groups.
+pub fn sample_db() -> Db {
+    let mut db = Db::new();
+    db.entry("name".to_string())
+        .or_default()
+        .insert("name".to_string(), "test".to_string());
+    insert_data(&mut db, "users", "alice", "1".to_string(), true).unwrap();
+    insert_data(&mut db, "users", "bob", "2".to_string(), true).unwrap();
+    insert_data(
+        &mut db,
+        "settings",
+        "theme",
+        "dark, with\nnewlines".to_string(),
+        true,
+    )
+    .unwrap();
+    db
+}
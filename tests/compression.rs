@@ -0,0 +1,157 @@
+#![cfg(feature = "compression")]
+
+mod common;
+
+use std::borrow::Cow;
+
+use plain_text_db_rust::{
+    clear, clear_group, copy_group, db_stats, from_bytes_auto, get_data, insert_data,
+    load_from_dir, read_as, read_batch, read_decompressed, read_value, rename_data, rename_group,
+    retain_groups, save_to_dir, to_compressed_bytes, CompressionLevel, Db, DbConfig, ErrorKind,
+    KvStore,
+};
+
+#[test]
+fn compressed_round_trip() {
+    let db = common::sample_db();
+    let bytes = to_compressed_bytes(&db, CompressionLevel::Best);
+    assert_eq!(from_bytes_auto(&bytes).unwrap(), db);
+}
+
+#[test]
+fn huge_length_in_the_header_is_corrupt_not_a_panic() {
+    let mut bytes = to_compressed_bytes(&common::sample_db(), CompressionLevel::Fast);
+    bytes[4..12].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(
+        from_bytes_auto(&bytes).unwrap_err().kind(),
+        ErrorKind::Corrupt
+    );
+}
+
+#[test]
+fn short_length_in_the_header_is_corrupt() {
+    let mut bytes = to_compressed_bytes(&common::sample_db(), CompressionLevel::Fast);
+    bytes[4..12].copy_from_slice(&3u64.to_le_bytes());
+    assert_eq!(
+        from_bytes_auto(&bytes).unwrap_err().kind(),
+        ErrorKind::Corrupt
+    );
+}
+
+fn compressing_config() -> DbConfig {
+    DbConfig {
+        compress_values_over: Some(16),
+        ..DbConfig::default()
+    }
+}
+
+fn has_marks(db: &Db) -> bool {
+    db["name"].keys().any(|key| key.starts_with("compressed:"))
+}
+
+#[test]
+fn writing_plain_data_over_a_compressed_entry_drops_the_mark() {
+    let mut db = common::sample_db();
+    let blob = "x".repeat(1000);
+    compressing_config()
+        .insert(&mut db, "blobs", "big", blob.clone())
+        .unwrap();
+    assert_ne!(db["blobs"]["big"], blob);
+    assert_eq!(read_decompressed(&db, "blobs", "big").unwrap(), blob);
+    insert_data(&mut db, "blobs", "big", "plain".to_string(), false).unwrap();
+    assert_eq!(read_decompressed(&db, "blobs", "big").unwrap(), "plain");
+    assert!(!has_marks(&db));
+}
+
+#[test]
+fn renames_and_copies_keep_compressed_entries_readable() {
+    let mut db = common::sample_db();
+    let blob = "y".repeat(1000);
+    compressing_config()
+        .insert(&mut db, "blobs", "big", blob.clone())
+        .unwrap();
+    rename_data(&mut db, "blobs", "big", "renamed", false).unwrap();
+    assert_eq!(read_decompressed(&db, "blobs", "renamed").unwrap(), blob);
+    rename_group(&mut db, "blobs", "moved", false).unwrap();
+    assert_eq!(read_decompressed(&db, "moved", "renamed").unwrap(), blob);
+    copy_group(&mut db, "moved", "copied", false).unwrap();
+    assert_eq!(read_decompressed(&db, "copied", "renamed").unwrap(), blob);
+    assert_eq!(read_decompressed(&db, "moved", "renamed").unwrap(), blob);
+}
+
+#[test]
+fn clearing_drops_the_marks() {
+    let mut db = common::sample_db();
+    let config = compressing_config();
+    for group_name in ["a", "b", "c"] {
+        config
+            .insert(&mut db, group_name, "big", "z".repeat(100))
+            .unwrap();
+    }
+    clear_group(&mut db, "a").unwrap();
+    retain_groups(&mut db, &["c"]);
+    assert!(db["name"].keys().any(|key| key.ends_with(":1:c:big")));
+    assert_eq!(
+        db["name"]
+            .keys()
+            .filter(|key| key.starts_with("compressed:"))
+            .count(),
+        1
+    );
+    clear(&mut db);
+    assert!(!has_marks(&db));
+    assert_eq!(db["name"]["name"], "test");
+}
+
+#[test]
+fn stats_tell_compressed_and_uncompressed_sizes_apart() {
+    let mut db = common::sample_db();
+    compressing_config()
+        .insert(&mut db, "blobs", "big", "w".repeat(1000))
+        .unwrap();
+    let stats = db_stats(&db);
+    let blobs = &stats.groups[0];
+    assert_eq!(blobs.compressed_entries, 1);
+    assert_eq!(blobs.uncompressed_bytes, 1000);
+    assert!(blobs.data_bytes < 1000);
+    assert_eq!(stats.total_uncompressed_bytes, 1000 + 21);
+    assert!(stats.to_string().contains("1021 uncompressed"));
+}
+
+#[test]
+fn compressed_entries_stay_compressed_after_a_dir_reload() {
+    let dir = common::temp_dir("compressed_entries_stay_compressed_after_a_dir_reload");
+    let mut db = common::sample_db();
+    let long = "a fairly long value that gets compressed".repeat(4);
+    compressing_config()
+        .insert(&mut db, "docs", "readme", long.clone())
+        .unwrap();
+    save_to_dir(&db, &dir).unwrap();
+    let loaded = load_from_dir(&dir).unwrap();
+    assert_eq!(loaded, db);
+    assert_eq!(read_decompressed(&loaded, "docs", "readme").unwrap(), long);
+}
+
+#[test]
+fn every_read_decompresses() {
+    let mut db = common::sample_db();
+    let numbers = format!("[{}]", vec!["12345"; 100].join(","));
+    compressing_config()
+        .insert(&mut db, "blobs", "numbers", numbers.clone())
+        .unwrap();
+    assert_ne!(db["blobs"]["numbers"], numbers);
+    assert_eq!(get_data(&db, "blobs", "numbers").unwrap(), numbers);
+    assert_eq!(read_as::<String>(&db, "blobs", "numbers").unwrap(), numbers);
+    assert_eq!(
+        read_batch::<String>(&db, "blobs", &["numbers", "missing"]).unwrap(),
+        [Some(numbers.clone()), None]
+    );
+    let value = read_value(&db, "blobs", "numbers", true).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 100);
+    assert_eq!(db.get_raw("blobs", "numbers").unwrap(), numbers);
+    // Uncompressed entries are still borrowed.
+    assert!(matches!(
+        get_data(&db, "users", "alice").unwrap(),
+        Cow::Borrowed("1")
+    ));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use plain_text_db_rust::{
+    enable_history, from_plain_text_with_config, to_plain_text, DbConfig, ErrorKind,
+};
+
+fn short_keys() -> DbConfig {
+    DbConfig {
+        max_key_len: 8,
+        ..DbConfig::default()
+    }
+}
+
+#[test]
+fn what_insert_accepted_loads_again() {
+    let config = short_keys();
+    let mut db = common::sample_db();
+    config
+        .insert(&mut db, "accounts", "username", "x".to_string())
+        .unwrap();
+    enable_history(&mut db, "accounts", 3);
+    let loaded = from_plain_text_with_config(&to_plain_text(&db), &config).unwrap();
+    assert_eq!(loaded, db);
+}
+
+#[test]
+fn loaded_names_over_the_limit_are_refused() {
+    let mut db = common::sample_db();
+    db.get_mut("users")
+        .unwrap()
+        .insert("much_too_long".to_string(), "1".to_string());
+    let why = from_plain_text_with_config(&to_plain_text(&db), &short_keys()).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::InvalidKey);
+}
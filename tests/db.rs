@@ -0,0 +1,152 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use plain_text_db_rust::{
+    append_data, create_group, entry_metadata, get_data, get_meta, increment, increment_f64,
+    insert_data, insert_tagged, load_db, merge_from, read_tagged, remove_prefix, save_to_file,
+    schema_version, set_meta, set_schema_version, Db, ErrorKind, ImportConflict,
+};
+
+#[test]
+fn load_db_keeps_the_metadata() {
+    let dir = common::temp_dir("load_db_keeps_the_metadata");
+    let mut db = common::sample_db();
+    set_schema_version(&mut db, 3);
+    set_meta(&mut db, "owner", "ops");
+    save_to_file(&db, dir.join("db.pdbr")).unwrap();
+    let base = dir.join("db");
+    let loaded = load_db(base.to_str().unwrap());
+    assert_eq!(schema_version(&loaded), 3);
+    assert_eq!(
+        get_meta::<String>(&loaded, "owner").unwrap().as_deref(),
+        Some("ops")
+    );
+    assert_eq!(loaded["name"]["name"], base.to_str().unwrap());
+}
+
+#[test]
+fn append_data_returns_a_failed_save_instead_of_panicking() {
+    let dir = common::temp_dir("append_data_returns_a_failed_save_instead_of_panicking");
+    let mut db = common::sample_db();
+    // <name>.pdbr lands in a directory that doesn't exist.
+    let name = dir.join("missing").join("db");
+    db.get_mut("name")
+        .unwrap()
+        .insert("name".to_string(), name.to_str().unwrap().to_string());
+    let why = append_data(db, "g".to_string(), "k".to_string(), "v".to_string()).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Io);
+}
+
+#[test]
+fn only_one_of_many_concurrent_create_group_calls_creates_it() {
+    let db = Arc::new(Mutex::new(common::sample_db()));
+    let threads: Vec<_> = (0..16)
+        .map(|_| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || create_group(&mut db.lock().unwrap(), "jobs"))
+        })
+        .collect();
+    let created = threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .filter(|&created| created)
+        .count();
+    assert_eq!(created, 1);
+    assert!(!create_group(&mut db.lock().unwrap(), "users"));
+    assert!(!create_group(&mut db.lock().unwrap(), "name"));
+}
+
+#[test]
+fn increment_starts_missing_entries_at_zero() {
+    let mut db = common::sample_db();
+    assert_eq!(increment(&mut db, "users", "visits", 5).unwrap(), 5);
+    assert_eq!(increment(&mut db, "users", "visits", -2).unwrap(), 3);
+    assert_eq!(db["users"]["visits"], "3");
+    assert_eq!(increment(&mut db, "users", "alice", 1).unwrap(), 2);
+}
+
+#[test]
+fn increment_leaves_data_that_isnt_a_number_alone() {
+    let mut db = common::sample_db();
+    let why = increment(&mut db, "settings", "theme", 1).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Parse);
+    assert_eq!(db["settings"]["theme"], "dark, with\nnewlines");
+}
+
+#[test]
+fn increment_refuses_to_overflow() {
+    let mut db = common::sample_db();
+    db.get_mut("users")
+        .unwrap()
+        .insert("max".to_string(), i64::MAX.to_string());
+    let why = increment(&mut db, "users", "max", 1).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Overflow);
+    assert_eq!(db["users"]["max"], i64::MAX.to_string());
+}
+
+#[test]
+fn increment_f64_adds_fractions() {
+    let mut db = common::sample_db();
+    assert_eq!(increment_f64(&mut db, "users", "score", 0.5).unwrap(), 0.5);
+    assert_eq!(
+        increment_f64(&mut db, "users", "score", 1.25).unwrap(),
+        1.75
+    );
+}
+
+#[test]
+fn get_data_borrows_large_data_without_copying_it() {
+    let mut db = common::sample_db();
+    let blob = format!("\"{}\"", "x".repeat(8 * 1024 * 1024));
+    let stored = blob.as_ptr();
+    insert_data(&mut db, "blobs", "big", blob, true).unwrap();
+    let data = get_data(&db, "blobs", "big").unwrap();
+    assert_eq!(data.as_ptr(), stored);
+    // Types that borrow from the data parse in place too.
+    let parsed: &str = serde_json::from_str(&data).unwrap();
+    assert_eq!(parsed.len(), 8 * 1024 * 1024);
+    assert!(data.as_bytes().as_ptr_range().contains(&parsed.as_ptr()));
+}
+
+#[test]
+fn remove_prefix_returns_how_many_it_removed() {
+    let mut db = common::sample_db();
+    for data_name in ["tmp_a", "tmp_b", "tmp_c"] {
+        insert_data(&mut db, "users", data_name, "0".to_string(), false).unwrap();
+    }
+    assert_eq!(remove_prefix(&mut db, "users", "tmp_").unwrap(), 3);
+    let mut left: Vec<&String> = db["users"].keys().collect();
+    left.sort();
+    assert_eq!(left, ["alice", "bob"]);
+    assert_eq!(remove_prefix(&mut db, "users", "tmp_").unwrap(), 0);
+}
+
+#[test]
+fn merge_from_creates_the_groups_it_counts() {
+    let mut db = common::sample_db();
+    let mut other = Db::new();
+    other.insert("empty".to_string(), Default::default());
+    let report = merge_from(&mut db, &other, ImportConflict::Error).unwrap();
+    assert_eq!(report.groups_created, 1);
+    assert!(db["empty"].is_empty());
+}
+
+#[test]
+fn insert_tagged_checks_the_group_like_insert_data() {
+    let mut db = common::sample_db();
+    let why = insert_tagged(&mut db, "missing", "n", &5u8, false).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::GroupNotFound);
+    let why = insert_tagged(&mut db, "name", "name", &5u8, true).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::GroupNotFound);
+    assert_eq!(db["name"]["name"], "test");
+    assert_eq!(
+        insert_tagged(&mut db, "numbers", "n", &5u8, true).unwrap(),
+        None
+    );
+    assert_eq!(read_tagged::<u8>(&db, "numbers", "n").unwrap(), 5);
+    let why = read_tagged::<i64>(&db, "numbers", "n").unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::TypeMismatch);
+    assert!(entry_metadata(&db, "numbers", "n").unwrap().modified > 0);
+}
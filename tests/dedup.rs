@@ -0,0 +1,66 @@
+mod common;
+
+use std::sync::Arc;
+
+use plain_text_db_rust::{DedupDb, KvStore};
+
+fn blob() -> String {
+    "default config ".repeat(1000)
+}
+
+#[test]
+fn identical_data_is_kept_once() {
+    let mut db = DedupDb::from_db(common::sample_db());
+    let before = db.pool_len();
+    db.put("records", "a", blob()).unwrap();
+    db.put("records", "b", blob()).unwrap();
+    assert_eq!(db.pool_len(), before + 1);
+    let a = db.get_shared("records", "a").unwrap();
+    let b = db.get_shared("records", "b").unwrap();
+    assert!(Arc::ptr_eq(&a, &b));
+}
+
+#[test]
+fn data_goes_when_the_last_entry_holding_it_does() {
+    let mut db = DedupDb::from_db(common::sample_db());
+    let before = db.pool_len();
+    db.put("records", "a", blob()).unwrap();
+    db.put("records", "b", blob()).unwrap();
+    db.put("other", "c", blob()).unwrap();
+    db.remove_data_entry("records", "a").unwrap();
+    assert_eq!(db.pool_len(), before + 1);
+    // Overwriting drops the old data's reference too.
+    db.put("records", "b", "small".to_string()).unwrap();
+    assert_eq!(db.pool_len(), before + 2);
+    db.remove_group("other").unwrap();
+    assert_eq!(db.pool_len(), before + 1);
+    db.remove_data_entry("records", "b").unwrap();
+    assert_eq!(db.pool_len(), before);
+}
+
+#[test]
+fn a_shared_reference_doesnt_keep_data_in_the_pool() {
+    let mut db = DedupDb::from_db(common::sample_db());
+    let before = db.pool_len();
+    db.put("records", "a", blob()).unwrap();
+    let held = db.get_shared("records", "a").unwrap();
+    db.remove_data_entry("records", "a").unwrap();
+    assert_eq!(db.pool_len(), before);
+    assert_eq!(*held, blob());
+}
+
+#[test]
+fn saved_text_writes_shared_data_once_and_shares_it_again() {
+    let mut db = DedupDb::from_db(common::sample_db());
+    for data_name in ["a", "b", "c"] {
+        db.put("records", data_name, blob()).unwrap();
+    }
+    let text = db.to_plain_text();
+    assert_eq!(text.matches(&blob()).count(), 1);
+    let loaded = DedupDb::from_plain_text(&text).unwrap();
+    assert_eq!(loaded.to_db(), db.to_db());
+    assert_eq!(loaded.pool_len(), db.pool_len());
+    let a = loaded.get_shared("records", "a").unwrap();
+    let c = loaded.get_shared("records", "c").unwrap();
+    assert!(Arc::ptr_eq(&a, &c));
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::time::Duration;
+
+use plain_text_db_rust::{
+    get_data, insert_with_ttl, load_from_dir, remove_data_entry_soft, save_to_dir, ErrorKind,
+};
+
+#[test]
+fn a_reload_keeps_the_entry_metadata() {
+    let dir = common::temp_dir("a_reload_keeps_the_entry_metadata");
+    let mut db = common::sample_db();
+    insert_with_ttl(
+        &mut db,
+        "sessions",
+        "old",
+        "1".to_string(),
+        Duration::ZERO,
+        true,
+    )
+    .unwrap();
+    insert_with_ttl(
+        &mut db,
+        "sessions",
+        "new",
+        "2".to_string(),
+        Duration::from_secs(3600),
+        true,
+    )
+    .unwrap();
+    remove_data_entry_soft(&mut db, "users", "bob").unwrap();
+    save_to_dir(&db, &dir).unwrap();
+    let loaded = load_from_dir(&dir).unwrap();
+    assert_eq!(loaded, db);
+    assert_eq!(
+        get_data(&loaded, "sessions", "old").unwrap_err().kind(),
+        ErrorKind::DataNotFound
+    );
+    assert_eq!(get_data(&loaded, "sessions", "new").unwrap(), "2");
+}
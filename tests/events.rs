@@ -0,0 +1,87 @@
+mod common;
+
+use plain_text_db_rust::{DbEvent, KvStore, NotifyingStore};
+
+fn written(group_name: &str, data_name: &str, data: &str) -> DbEvent {
+    DbEvent::DataWritten {
+        group_name: group_name.to_string(),
+        data_name: data_name.to_string(),
+        data: data.to_string(),
+    }
+}
+
+#[test]
+fn subscribers_get_every_write_in_order() {
+    let mut store = NotifyingStore::new(common::sample_db());
+    let events = store.subscribe();
+    assert!(store.create_group("jobs"));
+    assert!(!store.create_group("jobs"));
+    store.put("jobs", "a", "1".to_string()).unwrap();
+    store.remove_data_entry("jobs", "a").unwrap();
+    assert!(store.remove_data_entry("jobs", "a").is_none());
+    store.remove_group("jobs").unwrap();
+    let received: Vec<DbEvent> = events.try_iter().collect();
+    assert_eq!(
+        received,
+        vec![
+            DbEvent::GroupCreated {
+                group_name: "jobs".to_string()
+            },
+            written("jobs", "a", "1"),
+            DbEvent::DataRemoved {
+                group_name: "jobs".to_string(),
+                data_name: "a".to_string()
+            },
+            DbEvent::GroupRemoved {
+                group_name: "jobs".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn group_subscribers_only_get_their_group() {
+    let mut store = NotifyingStore::new(common::sample_db());
+    let users = store.subscribe_group("users");
+    store.put("settings", "theme", "light".to_string()).unwrap();
+    store.put("users", "carol", "3".to_string()).unwrap();
+    let received: Vec<DbEvent> = users.try_iter().collect();
+    assert_eq!(received, vec![written("users", "carol", "3")]);
+}
+
+#[test]
+fn a_slow_subscriber_misses_events_without_blocking_writes() {
+    let mut store = NotifyingStore::new(common::sample_db());
+    let events = store.subscribe();
+    for i in 0..2000 {
+        store.put("users", &i.to_string(), i.to_string()).unwrap();
+    }
+    let received: Vec<DbEvent> = events.try_iter().collect();
+    assert_eq!(received.len(), 1024);
+    assert_eq!(received[0], written("users", "0", "0"));
+    store.put("users", "last", "x".to_string()).unwrap();
+    assert_eq!(events.try_recv().unwrap(), DbEvent::Missed(976));
+    assert_eq!(events.try_recv().unwrap(), written("users", "last", "x"));
+    assert_eq!(events.try_recv(), None);
+}
+
+#[test]
+fn missed_events_are_reported_after_the_store_is_gone() {
+    let mut store = NotifyingStore::new(common::sample_db());
+    let events = store.subscribe();
+    for i in 0..1030 {
+        store.put("users", &i.to_string(), i.to_string()).unwrap();
+    }
+    drop(store);
+    let received: Vec<DbEvent> = events.iter().collect();
+    assert_eq!(received.len(), 1025);
+    assert_eq!(received[1024], DbEvent::Missed(6));
+}
+
+#[test]
+fn dropped_subscribers_dont_stop_writes() {
+    let mut store = NotifyingStore::new(common::sample_db());
+    drop(store.subscribe());
+    store.put("users", "carol", "3".to_string()).unwrap();
+    assert_eq!(store.get_raw("users", "carol").unwrap(), "3");
+}
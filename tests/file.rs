@@ -0,0 +1,71 @@
+mod common;
+
+use plain_text_db_rust::{
+    atomic_save, from_plain_text, get_data, insert_data, load_from_file, save_to_file,
+    to_plain_text, Db, ErrorKind,
+};
+
+#[test]
+fn save_and_load_round_trip() {
+    let dir = common::temp_dir("save_and_load_round_trip");
+    let path = dir.join("db.pdbr");
+    let db = common::sample_db();
+    save_to_file(&db, &path).unwrap();
+    let loaded = load_from_file(&path).unwrap();
+    assert_eq!(loaded, db);
+    assert_eq!(
+        get_data(&loaded, "settings", "theme").unwrap(),
+        "dark, with\nnewlines"
+    );
+}
+
+#[test]
+fn missing_file_is_not_found_and_garbage_is_corrupt() {
+    let dir = common::temp_dir("missing_file_is_not_found_and_garbage_is_corrupt");
+    let missing = load_from_file(dir.join("missing.pdbr")).unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::NotFound);
+    let garbage = dir.join("garbage.pdbr");
+    std::fs::write(&garbage, "%not a database\n").unwrap();
+    let corrupt = load_from_file(&garbage).unwrap_err();
+    assert_eq!(corrupt.kind(), ErrorKind::Corrupt);
+}
+
+#[test]
+fn database_without_a_name_round_trips() {
+    let mut db = Db::new();
+    insert_data(&mut db, "g", "k", "v".to_string(), true).unwrap();
+    assert_eq!(from_plain_text(&to_plain_text(&db)).unwrap(), db);
+    assert_eq!(
+        from_plain_text(&to_plain_text(&Db::new())).unwrap(),
+        Db::new()
+    );
+}
+
+#[test]
+fn empty_group_and_database_names_round_trip() {
+    let mut db = common::sample_db();
+    db.get_mut("name")
+        .unwrap()
+        .insert("name".to_string(), String::new());
+    insert_data(&mut db, "", "k", "v".to_string(), true).unwrap();
+    insert_data(&mut db, "", "", "".to_string(), true).unwrap();
+    assert_eq!(from_plain_text(&to_plain_text(&db)).unwrap(), db);
+}
+
+#[test]
+fn failed_atomic_save_leaves_the_original_intact() {
+    let dir = common::temp_dir("failed_atomic_save_leaves_the_original_intact");
+    let path = dir.join("db.pdbr");
+    atomic_save(&common::sample_db(), &path).unwrap();
+    let before = std::fs::read(&path).unwrap();
+    // The temp file can't be created, so the save fails before the rename.
+    std::fs::create_dir(path.with_extension("tmp")).unwrap();
+    let mut changed = common::sample_db();
+    insert_data(&mut changed, "users", "carol", "3".to_string(), true).unwrap();
+    assert_eq!(
+        atomic_save(&changed, &path).unwrap_err().kind(),
+        ErrorKind::Io
+    );
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+    assert_eq!(load_from_file(&path).unwrap(), common::sample_db());
+}
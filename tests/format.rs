@@ -0,0 +1,78 @@
+mod common;
+
+use plain_text_db_rust::{
+    from_plain_text, get_data, insert_data, to_plain_text, Db, ErrorKind, FORMAT_VERSION,
+};
+
+#[test]
+fn file_without_the_magic_is_refused() {
+    let why = from_plain_text("%sqlite 3\nname\n").unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Corrupt);
+    assert_eq!(why.to_string(), "not a plain-text-db file");
+}
+
+#[test]
+fn newer_format_is_refused() {
+    let text = to_plain_text(&common::sample_db()).replacen(
+        &format!("%pdbr {}", FORMAT_VERSION),
+        &format!("%pdbr {}", FORMAT_VERSION + 1),
+        1,
+    );
+    let why = from_plain_text(&text).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::UnsupportedVersion);
+    assert!(why.to_string().contains("newer version"));
+}
+
+#[test]
+fn legacy_file_without_a_header_loads() {
+    let db = from_plain_text("legacy\nusers\nalice,1\n").unwrap();
+    assert_eq!(db["name"]["name"], "legacy");
+    assert_eq!(get_data(&db, "users", "alice").unwrap(), "1");
+}
+
+#[test]
+fn flipped_byte_is_a_checksum_mismatch() {
+    let mut bytes = to_plain_text(&common::sample_db()).into_bytes();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0x01;
+    let why = from_plain_text(std::str::from_utf8(&bytes).unwrap()).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::ChecksumMismatch);
+    assert_eq!(why.to_string(), "checksum mismatch, file is corrupt");
+}
+
+#[test]
+fn format_1_file_without_a_checksum_loads() {
+    let db = from_plain_text("%pdbr 1\nold\nusers\nalice,1\n").unwrap();
+    assert_eq!(get_data(&db, "users", "alice").unwrap(), "1");
+}
+
+#[test]
+fn same_content_gives_the_same_bytes() {
+    let mut forwards = Db::new();
+    let mut backwards = Db::new();
+    let entries: Vec<(String, String)> = (0..50)
+        .map(|i| (format!("g{}", i % 7), format!("k{}", i)))
+        .collect();
+    for (group_name, data_name) in &entries {
+        insert_data(
+            &mut forwards,
+            group_name,
+            data_name,
+            data_name.clone(),
+            true,
+        )
+        .unwrap();
+    }
+    for (group_name, data_name) in entries.iter().rev() {
+        insert_data(
+            &mut backwards,
+            group_name,
+            data_name,
+            data_name.clone(),
+            true,
+        )
+        .unwrap();
+    }
+    assert_eq!(to_plain_text(&forwards), to_plain_text(&backwards));
+    assert_eq!(to_plain_text(&forwards), to_plain_text(&forwards.clone()));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use plain_text_db_rust::{
+    enable_history, from_plain_text, increment, insert_data, read_history, remove_group,
+    rename_group, revert, to_plain_text, KvStore,
+};
+
+fn values(history: Vec<(u64, i64)>) -> Vec<i64> {
+    history.into_iter().map(|(_, value)| value).collect()
+}
+
+#[test]
+fn every_write_keeps_history_once_enabled() {
+    let mut db = common::sample_db();
+    enable_history(&mut db, "users", 2);
+    insert_data(&mut db, "users", "alice", "10".to_string(), false).unwrap();
+    db.put("users", "alice", "11".to_string()).unwrap();
+    increment(&mut db, "users", "alice", 1).unwrap();
+    // Only the last two replaced values are kept.
+    let history = read_history::<i64>(&db, "users", "alice").unwrap();
+    assert_eq!(values(history), [10, 11]);
+    assert_eq!(revert(&mut db, "users", "alice", 2).unwrap(), "12");
+    assert_eq!(db["users"]["alice"], "10");
+    assert!(values(read_history(&db, "users", "alice").unwrap()).is_empty());
+    // Groups without it enabled keep nothing.
+    insert_data(&mut db, "settings", "theme", "light".to_string(), false).unwrap();
+    assert!(!db["name"].keys().any(|key| key.starts_with("history:")));
+}
+
+#[test]
+fn history_is_saved_with_the_database() {
+    let mut db = common::sample_db();
+    enable_history(&mut db, "users", 3);
+    insert_data(&mut db, "users", "bob", "20".to_string(), false).unwrap();
+    let loaded = from_plain_text(&to_plain_text(&db)).unwrap();
+    assert_eq!(values(read_history(&loaded, "users", "bob").unwrap()), [2]);
+}
+
+#[test]
+fn the_setting_moves_and_goes_with_its_group() {
+    let mut db = common::sample_db();
+    enable_history(&mut db, "users", 3);
+    rename_group(&mut db, "users", "people", false).unwrap();
+    insert_data(&mut db, "people", "alice", "5".to_string(), false).unwrap();
+    assert_eq!(values(read_history(&db, "people", "alice").unwrap()), [1]);
+    remove_group(&mut db, "people").unwrap();
+    insert_data(&mut db, "people", "alice", "6".to_string(), true).unwrap();
+    insert_data(&mut db, "people", "alice", "7".to_string(), false).unwrap();
+    assert!(values(read_history(&db, "people", "alice").unwrap()).is_empty());
+    assert!(!db["name"]
+        .keys()
+        .any(|key| key.starts_with("history_keep:")));
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use plain_text_db_rust::{Error, ErrorKind, HookedStore, KvStore, WalOp, WriteHooks};
+
+fn numbers_only(op: &WalOp) -> Result<(), Error> {
+    match op {
+        WalOp::Append {
+            group_name, data, ..
+        } if group_name == "users" && data.parse::<u32>().is_err() => Err(Error::new(
+            ErrorKind::SchemaViolation,
+            format!("users must be numbers, not '{}'", data),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn a_refused_put_returns_the_hooks_error_and_writes_nothing() {
+    let mut hooks = WriteHooks::new();
+    hooks.add(numbers_only);
+    let mut store = HookedStore::new(common::sample_db(), hooks);
+    let why = store
+        .put("users", "carol", "three".to_string())
+        .unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::SchemaViolation);
+    assert_eq!(
+        store.get_raw("users", "carol").unwrap_err().kind(),
+        ErrorKind::DataNotFound
+    );
+    store.put("users", "carol", "3".to_string()).unwrap();
+    assert_eq!(store.get_raw("users", "carol").unwrap(), "3");
+}
+
+#[test]
+fn hooks_see_every_write_before_it_is_made() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut store = HookedStore::new(common::sample_db(), WriteHooks::new());
+    let log = Arc::clone(&seen);
+    store.add_hook(move |op| {
+        log.lock().unwrap().push(format!("{:?}", op));
+        Ok(())
+    });
+    store.create_group("jobs");
+    store.put("jobs", "a", "1".to_string()).unwrap();
+    store.remove_data_entry("jobs", "a");
+    store.remove_group("jobs");
+    assert_eq!(seen.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn a_refused_remove_leaves_the_entry_and_says_why() {
+    let mut hooks = WriteHooks::new();
+    hooks.add(|op| match op {
+        WalOp::RemoveEntry { .. } | WalOp::RemoveGroup { .. } => Err(Error::new(
+            ErrorKind::Conflict,
+            "removes are off".to_string(),
+        )),
+        _ => Ok(()),
+    });
+    let mut store = HookedStore::new(common::sample_db(), hooks);
+    assert_eq!(store.remove_data_entry("users", "alice"), None);
+    assert_eq!(store.take_refusal().unwrap().kind(), ErrorKind::Conflict);
+    assert_eq!(store.remove_group("users"), None);
+    assert!(store.take_refusal().is_some());
+    assert_eq!(store.get_raw("users", "alice").unwrap(), "1");
+    assert!(store.create_group("jobs"));
+    assert!(store.take_refusal().is_none());
+}
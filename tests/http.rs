@@ -0,0 +1,89 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+
+use plain_text_db_rust::{load_from_file, serve_http, PersistentDb};
+use serde_json::{json, Value};
+
+fn start(path: &Path) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut db = PersistentDb::new(common::sample_db(), path);
+    // The server runs until the test process exits.
+    thread::spawn(move || serve_http(&mut db, listener));
+    addr
+}
+
+fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn reads_writes_and_removes_over_http() {
+    let path = common::temp_dir("reads_writes_and_removes_over_http").join("db.pdbr");
+    let addr = start(&path);
+    assert_eq!(
+        request(addr, "GET", "/groups", ""),
+        (200, json!(["settings", "users"]))
+    );
+    assert_eq!(
+        request(addr, "GET", "/groups/users", ""),
+        (200, json!({ "alice": "1", "bob": "2" }))
+    );
+    assert_eq!(
+        request(addr, "PUT", "/groups/users/carol", "3"),
+        (200, json!({ "replaced": null }))
+    );
+    assert_eq!(
+        request(addr, "GET", "/groups/users/carol", ""),
+        (200, json!("3"))
+    );
+    assert_eq!(
+        request(addr, "DELETE", "/groups/users/bob", ""),
+        (200, json!("2"))
+    );
+    assert_eq!(
+        request(addr, "DELETE", "/groups/settings", ""),
+        (200, json!({ "removed": 1 }))
+    );
+    assert_eq!(request(addr, "POST", "/save", ""), (200, json!(null)));
+    let saved = load_from_file(&path).unwrap();
+    assert_eq!(saved["users"]["carol"], "3");
+    assert!(!saved["users"].contains_key("bob"));
+    assert!(!saved.contains_key("settings"));
+}
+
+#[test]
+fn misses_are_404_with_the_error_kind() {
+    let path = common::temp_dir("misses_are_404_with_the_error_kind").join("db.pdbr");
+    let addr = start(&path);
+    let (status, body) = request(addr, "GET", "/groups/missing", "");
+    assert_eq!(status, 404);
+    assert_eq!(body["error"], "GroupNotFound");
+    let (status, body) = request(addr, "GET", "/groups/users/nobody", "");
+    assert_eq!(status, 404);
+    assert_eq!(body["error"], "DataNotFound");
+    let (status, body) = request(addr, "PUT", "/groups/users/carol", "{\"not\": 1}");
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "Parse");
+    assert_eq!(request(addr, "PATCH", "/groups", "").0, 405);
+}
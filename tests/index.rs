@@ -0,0 +1,59 @@
+mod common;
+
+use plain_text_db_rust::{IndexedStore, KvStore};
+
+fn parity(n: &u32) -> &'static str {
+    if n.is_multiple_of(2) {
+        "even"
+    } else {
+        "odd"
+    }
+}
+
+#[test]
+fn an_index_covers_existing_and_later_entries() {
+    let mut store = IndexedStore::new(common::sample_db());
+    store.create_index::<u32, _, _>("users", "parity", parity);
+    assert_eq!(
+        store.lookup_by_index("parity", &"odd"),
+        Some(vec!["alice".to_string()])
+    );
+    store.put("users", "carol", "3".to_string()).unwrap();
+    store.put("users", "dave", "4".to_string()).unwrap();
+    assert_eq!(
+        store.lookup_by_index("parity", &"odd"),
+        Some(vec!["alice".to_string(), "carol".to_string()])
+    );
+    assert_eq!(
+        store.lookup_by_index("parity", &"even"),
+        Some(vec!["bob".to_string(), "dave".to_string()])
+    );
+}
+
+#[test]
+fn rewrites_and_removes_move_entries_out_of_their_old_key() {
+    let mut store = IndexedStore::new(common::sample_db());
+    store.create_index::<u32, _, _>("users", "parity", parity);
+    store.put("users", "alice", "10".to_string()).unwrap();
+    assert_eq!(store.lookup_by_index("parity", &"odd"), Some(vec![]));
+    store.remove_data_entry("users", "bob");
+    assert_eq!(
+        store.lookup_by_index("parity", &"even"),
+        Some(vec!["alice".to_string()])
+    );
+    // Data that doesn't parse isn't indexed, and leaves its old key.
+    store.put("users", "alice", "lots".to_string()).unwrap();
+    assert_eq!(store.lookup_by_index("parity", &"even"), Some(vec![]));
+    store.remove_group("users");
+    assert_eq!(store.lookup_by_index("parity", &"even"), Some(vec![]));
+}
+
+#[test]
+fn unknown_and_dropped_indexes_are_none() {
+    let mut store = IndexedStore::new(common::sample_db());
+    assert_eq!(store.lookup_by_index("parity", &"odd"), None);
+    store.create_index::<u32, _, _>("users", "parity", parity);
+    assert!(store.drop_index("parity"));
+    assert_eq!(store.lookup_by_index("parity", &"odd"), None);
+    assert!(!store.drop_index("parity"));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::time::Duration;
+
+use plain_text_db_rust::{
+    entry_metadata, get_data, import_ndjson, insert_with_ttl, remove_data_entry_soft, ErrorKind,
+};
+
+#[test]
+fn imported_entries_replace_the_old_metadata() {
+    let mut db = common::sample_db();
+    insert_with_ttl(
+        &mut db,
+        "sessions",
+        "a",
+        "old".to_string(),
+        Duration::ZERO,
+        true,
+    )
+    .unwrap();
+    remove_data_entry_soft(&mut db, "users", "bob").unwrap();
+    let ndjson = concat!(
+        r#"{"group": "sessions", "data_name": "a", "data": "new"}"#,
+        "\n",
+        r#"{"group": "users", "data_name": "bob", "data": 3}"#,
+        "\n",
+    );
+    assert_eq!(import_ndjson(&mut db, ndjson, true).unwrap(), 2);
+    assert_eq!(get_data(&db, "sessions", "a").unwrap(), "new");
+    let meta = entry_metadata(&db, "users", "bob").unwrap();
+    assert!(meta.modified > 0);
+    assert!(!db["name"].contains_key("deleted:5:users:bob"));
+}
+
+#[test]
+fn expired_entries_dont_block_an_import() {
+    let mut db = common::sample_db();
+    insert_with_ttl(
+        &mut db,
+        "sessions",
+        "a",
+        "old".to_string(),
+        Duration::ZERO,
+        true,
+    )
+    .unwrap();
+    let ndjson = r#"{"group": "sessions", "data_name": "a", "data": "new"}"#;
+    assert_eq!(import_ndjson(&mut db, ndjson, false).unwrap(), 1);
+    assert_eq!(get_data(&db, "sessions", "a").unwrap(), "new");
+    assert_eq!(import_ndjson(&mut db, ndjson, false).unwrap(), 0);
+}
+
+#[test]
+fn the_name_group_cant_be_imported() {
+    let mut db = common::sample_db();
+    let ndjson = r#"{"group": "name", "data_name": "name", "data": "other"}"#;
+    let why = import_ndjson(&mut db, ndjson, true).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Corrupt);
+    assert_eq!(db["name"]["name"], "test");
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use std::time::Duration;
+
+use plain_text_db_rust::{atomic_save, lock_path, save_to_file, try_lock_timeout, ErrorKind};
+
+#[test]
+fn saves_are_refused_without_the_guard() {
+    let dir = common::temp_dir("saves_are_refused_without_the_guard");
+    let path = dir.join("db.pdbr");
+    let db = common::sample_db();
+    atomic_save(&db, &path).unwrap();
+    let guard = lock_path(&path).unwrap();
+    assert_eq!(
+        atomic_save(&db, &path).unwrap_err().kind(),
+        ErrorKind::Locked
+    );
+    assert_eq!(
+        save_to_file(&db, &path).unwrap_err().kind(),
+        ErrorKind::Locked
+    );
+    guard.save(&db).unwrap();
+    assert_eq!(guard.load().unwrap(), db);
+    drop(guard);
+    atomic_save(&db, &path).unwrap();
+}
+
+#[test]
+fn second_lock_names_the_holder() {
+    let dir = common::temp_dir("second_lock_names_the_holder");
+    let path = dir.join("db.pdbr");
+    let _guard = lock_path(&path).unwrap();
+    let Err(why) = lock_path(&path) else {
+        panic!("locked twice");
+    };
+    assert_eq!(why.kind(), ErrorKind::Locked);
+    assert!(why.to_string().contains(&std::process::id().to_string()));
+    let Err(why) = try_lock_timeout(&path, Duration::from_millis(120)) else {
+        panic!("locked twice");
+    };
+    assert_eq!(why.kind(), ErrorKind::Locked);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::sync::Barrier;
+use std::thread;
+
+use plain_text_db_rust::{
+    deep_clone, get_data, insert_data, read_only, try_append_data, try_read_data, DbManager,
+    ErrorKind,
+};
+
+#[test]
+fn clones_of_a_handle_see_each_others_writes() {
+    let manager = DbManager::new(common::temp_dir(
+        "clones_of_a_handle_see_each_others_writes",
+    ));
+    let db = manager.open("shared").unwrap();
+    let clone = db.clone();
+    thread::spawn(move || {
+        insert_data(&mut clone.lock().unwrap(), "g", "k", "v".to_string(), true).unwrap();
+    })
+    .join()
+    .unwrap();
+    assert_eq!(get_data(&db.lock().unwrap(), "g", "k").unwrap(), "v");
+    // Opening the same name again hands out the same database too.
+    let reopened = manager.open("shared").unwrap();
+    assert_eq!(get_data(&reopened.lock().unwrap(), "g", "k").unwrap(), "v");
+}
+
+#[test]
+fn a_deep_clone_doesnt_see_later_writes() {
+    let manager = DbManager::new(common::temp_dir("a_deep_clone_doesnt_see_later_writes"));
+    let db = manager.open("shared").unwrap();
+    insert_data(&mut db.lock().unwrap(), "g", "k", "old".to_string(), true).unwrap();
+    let mut snapshot = deep_clone(&db);
+    insert_data(&mut db.lock().unwrap(), "g", "k", "new".to_string(), true).unwrap();
+    assert_eq!(snapshot["g"]["k"], "old");
+    insert_data(&mut snapshot, "g", "other", "x".to_string(), true).unwrap();
+    assert!(!db.lock().unwrap()["g"].contains_key("other"));
+}
+
+#[test]
+fn a_read_only_handle_sees_writes_made_through_the_other_handles() {
+    let manager = DbManager::new(common::temp_dir(
+        "a_read_only_handle_sees_writes_made_through_the_other_handles",
+    ));
+    let db = manager.open("shared").unwrap();
+    let reader = read_only(&db);
+    assert!(!reader.contains_group("g"));
+    insert_data(&mut db.lock().unwrap(), "g", "k", "7".to_string(), true).unwrap();
+    assert_eq!(reader.read_data::<i32>("g", "k").unwrap(), 7);
+    assert_eq!(
+        reader.read_batch::<i32>("g", &["k", "x"]).unwrap(),
+        [Some(7), None]
+    );
+    assert_eq!(reader.list_groups(), ["g"]);
+    assert!(reader.contains_data("g", "k"));
+    let snapshot = reader.snapshot();
+    insert_data(&mut db.lock().unwrap(), "g", "k", "8".to_string(), true).unwrap();
+    assert_eq!(reader.read_data::<i32>("g", "k").unwrap(), 8);
+    assert_eq!(snapshot["g"]["k"], "7");
+    assert!(reader.to_plain_text().contains("k,8"));
+}
+
+#[test]
+fn try_functions_fail_fast_while_the_handle_is_locked() {
+    let manager = DbManager::new(common::temp_dir(
+        "try_functions_fail_fast_while_the_handle_is_locked",
+    ));
+    let db = manager.open("shared").unwrap();
+    try_append_data(&db, "g", "k", &1).unwrap();
+    assert_eq!(try_read_data::<i32>(&db, "g", "k").unwrap(), 1);
+    let (locked, release) = (Barrier::new(2), Barrier::new(2));
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let _guard = db.lock().unwrap();
+            locked.wait();
+            release.wait();
+        });
+        locked.wait();
+        let why = try_read_data::<i32>(&db, "g", "k").unwrap_err();
+        assert_eq!(why.kind(), ErrorKind::WouldBlock);
+        let why = try_append_data(&db, "g", "k", &2).unwrap_err();
+        assert_eq!(why.kind(), ErrorKind::WouldBlock);
+        release.wait();
+    });
+    assert_eq!(try_read_data::<i32>(&db, "g", "k").unwrap(), 1);
+}
+
+#[test]
+fn a_failed_close_keeps_the_database_open() {
+    let dir = common::temp_dir("a_failed_close_keeps_the_database_open");
+    let manager = DbManager::new(dir.join("dbs"));
+    std::fs::create_dir(dir.join("dbs")).unwrap();
+    let db = manager.open("shared").unwrap();
+    insert_data(&mut db.lock().unwrap(), "g", "k", "v".to_string(), true).unwrap();
+    drop(db);
+    // Saving can't work while the directory is gone.
+    std::fs::remove_dir(dir.join("dbs")).unwrap();
+    let why = manager.close("shared").unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Io);
+    assert_eq!(manager.list_open(), ["shared"]);
+    std::fs::create_dir(dir.join("dbs")).unwrap();
+    assert!(manager.close("shared").unwrap());
+    assert!(manager.list_open().is_empty());
+    let reopened = manager.open("shared").unwrap();
+    assert_eq!(get_data(&reopened.lock().unwrap(), "g", "k").unwrap(), "v");
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use plain_text_db_rust::{
+    apply_migrations, get_data, insert_data, schema_version, set_schema_version, Db, Error,
+    ErrorKind, Migration,
+};
+
+fn add_email(db: &mut Db) -> Result<(), Error> {
+    insert_data(db, "users", "email", "none".to_string(), false)?;
+    Ok(())
+}
+
+fn copy_email_to_settings(db: &mut Db) -> Result<(), Error> {
+    // Needs add_email to have run first.
+    let email = get_data(db, "users", "email")?.into_owned();
+    insert_data(db, "settings", "contact", email, false)?;
+    Ok(())
+}
+
+fn always_fails(_: &mut Db) -> Result<(), Error> {
+    Err(Error::new(ErrorKind::Conflict, "not today".to_string()))
+}
+
+#[test]
+fn migrations_run_in_version_order() {
+    let mut db = common::sample_db();
+    // Listed out of order, run by version.
+    let migrations = [
+        Migration::new(2, 3, copy_email_to_settings),
+        Migration::new(0, 2, add_email),
+    ];
+    apply_migrations(&mut db, &migrations).unwrap();
+    assert_eq!(schema_version(&db), 3);
+    assert_eq!(db["settings"]["contact"], "none");
+    // Already at the last version, nothing runs again.
+    apply_migrations(&mut db, &migrations).unwrap();
+    assert_eq!(schema_version(&db), 3);
+}
+
+#[test]
+fn a_version_gap_is_refused() {
+    let mut db = common::sample_db();
+    set_schema_version(&mut db, 1);
+    let migrations = [
+        Migration::new(0, 1, add_email),
+        Migration::new(2, 3, copy_email_to_settings),
+    ];
+    let why = apply_migrations(&mut db, &migrations).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Migration);
+    assert!(why.to_string().contains("version 1"));
+    assert_eq!(schema_version(&db), 1);
+}
+
+#[test]
+fn a_failing_migration_says_which_one_it_was() {
+    let mut db = common::sample_db();
+    let migrations = [
+        Migration::new(0, 1, add_email),
+        Migration::new(1, 2, always_fails),
+    ];
+    let why = apply_migrations(&mut db, &migrations).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Migration);
+    assert!(why
+        .to_string()
+        .contains("migration 1 (1 -> 2) failed: not today"));
+    // The first one ran and is recorded.
+    assert_eq!(schema_version(&db), 1);
+}
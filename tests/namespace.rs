@@ -0,0 +1,32 @@
+mod common;
+
+use plain_text_db_rust::{get_data, namespace, ErrorKind, KvStore};
+
+#[test]
+fn namespaces_dont_collide() {
+    let mut plugin_a = namespace(common::sample_db(), "a");
+    plugin_a.put("config", "mode", "fast".to_string()).unwrap();
+    let mut plugin_b = namespace(plugin_a.into_inner(), "b");
+    plugin_b.put("config", "mode", "safe".to_string()).unwrap();
+    assert_eq!(plugin_b.list_groups(), vec!["config".to_string()]);
+    plugin_b.remove_group("config").unwrap();
+    let db = plugin_b.into_inner();
+    assert_eq!(get_data(&db, "a:config", "mode").unwrap(), "fast");
+    assert!(!db.contains_key("b:config"));
+    let plugin_a = namespace(db, "a");
+    assert_eq!(plugin_a.get_raw("config", "mode").unwrap(), "fast");
+    // The groups outside every namespace aren't seen from one.
+    assert_eq!(plugin_a.list_groups(), vec!["config".to_string()]);
+}
+
+#[test]
+fn group_names_with_the_separator_are_refused() {
+    let mut plugin_a = namespace(common::sample_db(), "a");
+    let why = plugin_a
+        .put("x:config", "mode", "escaped".to_string())
+        .unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::InvalidKey);
+    assert!(!plugin_a.create_group("b:config"));
+    assert_eq!(plugin_a.remove_group("b:config"), None);
+    assert!(!plugin_a.into_inner().contains_key("a:x:config"));
+}
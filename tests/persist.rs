@@ -0,0 +1,25 @@
+mod common;
+
+use plain_text_db_rust::{get_data, insert_data, open_persistent, PersistentDb};
+
+#[test]
+fn writes_are_there_after_shutdown_and_reopen() {
+    let path = common::temp_dir("writes_are_there_after_shutdown_and_reopen").join("db.pdbr");
+    let mut db = PersistentDb::new(common::sample_db(), &path);
+    insert_data(&mut db, "users", "carol", "3".to_string(), false).unwrap();
+    db.shutdown().unwrap();
+    let db = open_persistent(&path).unwrap();
+    assert_eq!(get_data(&db, "users", "carol").unwrap(), "3");
+    assert_eq!(get_data(&db, "users", "alice").unwrap(), "1");
+}
+
+#[test]
+fn dropping_without_shutdown_still_saves() {
+    let path = common::temp_dir("dropping_without_shutdown_still_saves").join("db.pdbr");
+    {
+        let mut db = PersistentDb::new(common::sample_db(), &path);
+        insert_data(&mut db, "users", "carol", "3".to_string(), false).unwrap();
+    }
+    let db = open_persistent(&path).unwrap();
+    assert_eq!(get_data(&db, "users", "carol").unwrap(), "3");
+}
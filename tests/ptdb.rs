@@ -0,0 +1,44 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn ptdb(path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ptdb"))
+        .arg("--file")
+        .arg(path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn set_creates_a_missing_file() {
+    let path = common::temp_dir("set_creates_a_missing_file").join("new.pdbr");
+    assert!(ptdb(&path, &["set", "g", "k", "v"]).status.success());
+    let get = ptdb(&path, &["get", "g", "k"]);
+    assert!(get.status.success());
+    assert_eq!(String::from_utf8(get.stdout).unwrap(), "v\n");
+}
+
+#[test]
+fn reads_of_a_missing_file_are_not_found() {
+    let path = common::temp_dir("reads_of_a_missing_file_are_not_found").join("new.pdbr");
+    assert_eq!(ptdb(&path, &["get", "g", "k"]).status.code(), Some(1));
+    assert_eq!(ptdb(&path, &["rm", "g"]).status.code(), Some(1));
+    assert!(!path.exists());
+}
+
+#[test]
+fn get_as_hex_shows_the_bytes() {
+    let path = common::temp_dir("get_as_hex_shows_the_bytes").join("db.pdbr");
+    assert!(ptdb(&path, &["set", "g", "k", "hé"]).status.success());
+    let get = ptdb(&path, &["get", "g", "k", "--as", "hex"]);
+    assert_eq!(String::from_utf8(get.stdout).unwrap(), "68c3a9\n");
+    assert_eq!(
+        ptdb(&path, &["set", "g", "k", "68", "--type", "hex"])
+            .status
+            .code(),
+        Some(2)
+    );
+}
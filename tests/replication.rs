@@ -0,0 +1,100 @@
+mod common;
+
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use plain_text_db_rust::{
+    sync_from_events, sync_from_ops, Db, ErrorKind, KvStore, NotifyingStore, PublishingStore,
+};
+
+// Everything but the name group, whose times depend on when each side wrote.
+fn groups(db: &Db) -> Db {
+    db.iter()
+        .filter(|(group_name, _)| *group_name != "name")
+        .map(|(group_name, group)| (group_name.clone(), group.clone()))
+        .collect()
+}
+
+fn mixed_writes(store: &mut impl KvStore, writer: usize, i: usize) {
+    let group_name = format!("g{}", i % 5);
+    let data_name = format!("w{}-{}", writer, i % 7);
+    match i % 10 {
+        0..=5 => {
+            store.put(&group_name, &data_name, i.to_string()).unwrap();
+        }
+        6 | 7 => {
+            store.remove_data_entry(&group_name, &data_name);
+        }
+        8 => {
+            store.create_group(&format!("empty{}", writer));
+        }
+        _ => {
+            store.remove_group(&format!("empty{}", writer));
+        }
+    }
+}
+
+#[test]
+fn a_follower_ends_up_with_the_primarys_groups() {
+    let mut primary = NotifyingStore::new(common::sample_db());
+    let events = primary.subscribe();
+    let primary = Arc::new(Mutex::new(primary));
+    let follower = thread::spawn(move || {
+        let mut follower = common::sample_db();
+        let applied = sync_from_events(&mut follower, &events).unwrap();
+        (follower, applied)
+    });
+    let writers: Vec<_> = (0..4)
+        .map(|writer| {
+            let primary = Arc::clone(&primary);
+            thread::spawn(move || {
+                for i in 0..100 {
+                    mixed_writes(&mut *primary.lock().unwrap(), writer, i);
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    // Dropping the store ends the subscription and so the follower's loop.
+    let primary = Arc::try_unwrap(primary)
+        .ok()
+        .unwrap()
+        .into_inner()
+        .unwrap()
+        .into_inner();
+    let (follower, applied) = follower.join().unwrap();
+    assert!(applied > 0);
+    assert_eq!(groups(&follower), groups(&primary));
+}
+
+#[test]
+fn a_follower_that_fell_behind_stops_with_lagged() {
+    let mut primary = NotifyingStore::new(common::sample_db());
+    let events = primary.subscribe();
+    for i in 0..2000 {
+        primary.put("users", &i.to_string(), i.to_string()).unwrap();
+    }
+    primary.put("users", "last", "x".to_string()).unwrap();
+    drop(primary);
+    let mut follower = common::sample_db();
+    let why = sync_from_events(&mut follower, &events).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Lagged);
+    // Everything before the gap was applied.
+    assert_eq!(follower["users"]["1023"], "1023");
+    assert!(!follower["users"].contains_key("1024"));
+}
+
+#[test]
+fn publishing_store_followers_get_every_op() {
+    let (ops, received) = mpsc::channel();
+    let mut primary = PublishingStore::new(common::sample_db(), ops);
+    for i in 0..300 {
+        mixed_writes(&mut primary, 0, i);
+    }
+    let primary = primary.into_inner();
+    let mut follower = common::sample_db();
+    sync_from_ops(&mut follower, &received);
+    assert_eq!(groups(&follower), groups(&primary));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use plain_text_db_rust::{db_stats, remove_data_entry, reserve_group, shrink_to_fit, ErrorKind};
+
+#[test]
+fn shrink_to_fit_gives_back_capacity_after_removing_most_entries() {
+    let mut db = common::sample_db();
+    for i in 0..10_000 {
+        db.get_mut("users")
+            .unwrap()
+            .insert(format!("user{}", i), i.to_string());
+    }
+    for i in 10..10_000 {
+        remove_data_entry(&mut db, "users", &format!("user{}", i));
+    }
+    let before = db["users"].capacity();
+    shrink_to_fit(&mut db);
+    let after = db["users"].capacity();
+    assert!(after * 10 < before, "{} -> {}", before, after);
+    assert_eq!(db["users"].len(), 12);
+}
+
+#[test]
+fn shrink_to_fit_trims_data() {
+    let mut db = common::sample_db();
+    let mut data = String::with_capacity(4096);
+    data.push_str("small");
+    db.get_mut("users")
+        .unwrap()
+        .insert("carol".to_string(), data);
+    shrink_to_fit(&mut db);
+    assert!(db["users"]["carol"].capacity() < 4096);
+}
+
+#[test]
+fn reserve_group_makes_room_before_a_bulk_insert() {
+    let mut db = common::sample_db();
+    reserve_group(&mut db, "users", 5_000).unwrap();
+    assert!(db["users"].capacity() >= 5_002);
+    let why = reserve_group(&mut db, "missing", 10).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::GroupNotFound);
+}
+
+#[test]
+fn stats_serialize_to_json() {
+    let stats = db_stats(&common::sample_db());
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+    assert_eq!(json["total_entries"], 3);
+    assert_eq!(json["total_data_bytes"], 2 + 19);
+    assert_eq!(json["groups"][0]["group_name"], "settings");
+    assert_eq!(json["groups"][1]["largest"]["data_name"], "alice");
+    assert_eq!(json["groups"][1]["largest"]["bytes"], 1);
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use std::time::Duration;
+
+use plain_text_db_rust::{
+    append_data, entry_metadata, for_each_entry, from_plain_text, get_data, insert_data,
+    insert_if_absent, insert_with_ttl, list_data_names, load_from_file, read_all, read_batch,
+    read_data, read_page, read_prefix, rename_data, snapshot_group, Db, ErrorKind, KvStore,
+};
+
+#[test]
+fn every_write_records_modified_and_keeps_created() {
+    let mut db = common::sample_db();
+    insert_data(&mut db, "users", "carol", "3".to_string(), false).unwrap();
+    let first = entry_metadata(&db, "users", "carol").unwrap();
+    assert!(first.created > 0);
+    assert_eq!(first.created, first.modified);
+    // Pretend the entry was written long ago.
+    let mut saved = db.clone();
+    for kind in ["created", "modified"] {
+        saved
+            .get_mut("name")
+            .unwrap()
+            .insert(format!("{}:5:users:carol", kind), "1".to_string());
+    }
+    let old = entry_metadata(&saved, "users", "carol").unwrap();
+    assert_eq!((old.created, old.modified), (1, 1));
+    saved.put("users", "carol", "4".to_string()).unwrap();
+    let rewritten = entry_metadata(&saved, "users", "carol").unwrap();
+    assert_eq!(rewritten.created, 1);
+    assert!(rewritten.modified >= first.modified);
+}
+
+#[test]
+fn append_data_saves_the_times() {
+    let dir = common::temp_dir("append_data_saves_the_times");
+    let mut db = common::sample_db();
+    let name = dir.join("times");
+    db.get_mut("name")
+        .unwrap()
+        .insert("name".to_string(), name.to_str().unwrap().to_string());
+    append_data(
+        db,
+        "users".to_string(),
+        "carol".to_string(),
+        "3".to_string(),
+    )
+    .unwrap();
+    let loaded = load_from_file(name.with_extension("pdbr")).unwrap();
+    assert!(entry_metadata(&loaded, "users", "carol").unwrap().modified > 0);
+}
+
+#[test]
+fn entries_without_times_read_as_zero() {
+    let db = from_plain_text("old\nusers\nalice,1\n").unwrap();
+    let meta = entry_metadata(&db, "users", "alice").unwrap();
+    assert_eq!((meta.created, meta.modified), (0, 0));
+}
+
+#[test]
+fn expired_entries_read_as_missing() {
+    let mut db = common::sample_db();
+    insert_with_ttl(
+        &mut db,
+        "users",
+        "session",
+        "s".to_string(),
+        Duration::ZERO,
+        false,
+    )
+    .unwrap();
+    let why = get_data(&db, "users", "session").unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::DataNotFound);
+    let why = read_data(db.clone(), "users".to_string(), "session".to_string()).unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::DataNotFound);
+    // Writing it again without a ttl gives an entry that doesn't expire.
+    insert_data(&mut db, "users", "session", "t".to_string(), false).unwrap();
+    assert_eq!(get_data(&db, "users", "session").unwrap(), "t");
+}
+
+#[test]
+fn rename_data_takes_the_expiry_along() {
+    let mut db = common::sample_db();
+    let day = Duration::from_secs(24 * 60 * 60);
+    insert_with_ttl(&mut db, "users", "old", "s".to_string(), day, false).unwrap();
+    rename_data(&mut db, "users", "old", "new", false).unwrap();
+    assert!(db["name"].contains_key("expires:5:users:new"));
+    assert!(!db["name"].contains_key("expires:5:users:old"));
+    assert!(!db["name"].contains_key("created:5:users:old"));
+}
+
+// sample_db with users/session written already expired.
+fn db_with_an_expired_entry() -> Db {
+    let mut db = common::sample_db();
+    insert_with_ttl(
+        &mut db,
+        "users",
+        "session",
+        "9".to_string(),
+        Duration::ZERO,
+        false,
+    )
+    .unwrap();
+    db
+}
+
+#[test]
+fn bulk_reads_leave_expired_entries_out() {
+    let db = db_with_an_expired_entry();
+    let everyone = vec![("alice".to_string(), 1), ("bob".to_string(), 2)];
+    assert_eq!(read_all::<i32>(&db, "users", false).unwrap(), everyone);
+    let snapshot = snapshot_group::<i32>(&db, "users").unwrap();
+    assert_eq!(snapshot.len(), 2);
+    assert!(!snapshot.contains_key("session"));
+    let mut seen = Vec::new();
+    for_each_entry(&db, "users", |data_name, _| {
+        seen.push(data_name.to_string())
+    })
+    .unwrap();
+    assert_eq!(seen, ["alice", "bob"]);
+    assert_eq!(
+        read_batch::<i32>(&db, "users", &["alice", "session"]).unwrap(),
+        [Some(1), None]
+    );
+    assert_eq!(read_prefix::<i32>(&db, "users", "s").unwrap(), []);
+    assert_eq!(
+        read_page::<i32>(&db, "users", None, 10).unwrap().items,
+        everyone
+    );
+    assert_eq!(list_data_names(&db, "users").unwrap(), ["alice", "bob"]);
+}
+
+#[test]
+fn an_expired_entry_doesnt_block_insert_if_absent() {
+    let mut db = db_with_an_expired_entry();
+    assert!(insert_if_absent(&mut db, "users", "session", &10).unwrap());
+    assert_eq!(get_data(&db, "users", "session").unwrap(), "10");
+    assert!(!insert_if_absent(&mut db, "users", "session", &11).unwrap());
+}
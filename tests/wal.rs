@@ -0,0 +1,88 @@
+mod common;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use plain_text_db_rust::{entry_metadata, get_data, open_with_wal, save_to_file, ErrorKind, WalOp};
+
+fn append(group_name: &str, data_name: &str, data: &str) -> WalOp {
+    WalOp::Append {
+        group_name: group_name.to_string(),
+        data_name: data_name.to_string(),
+        data: data.to_string(),
+    }
+}
+
+#[test]
+fn the_log_is_replayed_after_a_crash() {
+    let dir = common::temp_dir("the_log_is_replayed_after_a_crash");
+    let (snapshot, log) = (dir.join("db.pdbr"), dir.join("db.wal"));
+    save_to_file(&common::sample_db(), &snapshot).unwrap();
+    let (mut db, mut wal) = open_with_wal(&snapshot, &log).unwrap();
+    wal.log(&mut db, append("users", "carol", "3")).unwrap();
+    let remove = WalOp::RemoveEntry {
+        group_name: "users".to_string(),
+        data_name: "alice".to_string(),
+    };
+    wal.log(&mut db, remove).unwrap();
+    let drop_group = WalOp::RemoveGroup {
+        group_name: "settings".to_string(),
+    };
+    wal.log(&mut db, drop_group).unwrap();
+    // No checkpoint, the snapshot still has the old data.
+    drop(wal);
+    let (replayed, _) = open_with_wal(&snapshot, &log).unwrap();
+    assert_eq!(replayed["users"], db["users"]);
+    assert_eq!(get_data(&replayed, "users", "carol").unwrap(), "3");
+    assert!(
+        entry_metadata(&replayed, "users", "carol")
+            .unwrap()
+            .modified
+            > 0
+    );
+    assert!(!replayed.contains_key("settings"));
+    // What was forgotten along with the removes stays forgotten.
+    assert!(!replayed["name"].keys().any(|key| key.contains(":alice")));
+    assert!(!replayed["name"]
+        .keys()
+        .any(|key| key.contains(":settings:")));
+}
+
+#[test]
+fn a_torn_last_record_is_cut_from_the_log() {
+    let dir = common::temp_dir("a_torn_last_record_is_cut_from_the_log");
+    let (snapshot, log) = (dir.join("db.pdbr"), dir.join("db.wal"));
+    save_to_file(&common::sample_db(), &snapshot).unwrap();
+    let (mut db, mut wal) = open_with_wal(&snapshot, &log).unwrap();
+    wal.log(&mut db, append("users", "carol", "3")).unwrap();
+    drop(wal);
+    let intact_len = std::fs::metadata(&log).unwrap().len();
+    // A crash in the middle of writing the next record.
+    let mut file = OpenOptions::new().append(true).open(&log).unwrap();
+    file.write_all(&[40, 0, 0, 0, 1, 2, 3, 4, 1, 5]).unwrap();
+    drop(file);
+
+    let (mut db, mut wal) = open_with_wal(&snapshot, &log).unwrap();
+    assert_eq!(std::fs::metadata(&log).unwrap().len(), intact_len);
+    assert_eq!(get_data(&db, "users", "carol").unwrap(), "3");
+    // New records go after the intact ones.
+    wal.log(&mut db, append("users", "dave", "4")).unwrap();
+    drop(wal);
+    let (replayed, _) = open_with_wal(&snapshot, &log).unwrap();
+    assert_eq!(get_data(&replayed, "users", "carol").unwrap(), "3");
+    assert_eq!(get_data(&replayed, "users", "dave").unwrap(), "4");
+}
+
+#[test]
+fn ops_on_the_name_group_are_refused_before_they_are_logged() {
+    let dir = common::temp_dir("ops_on_the_name_group_are_refused_before_they_are_logged");
+    let (snapshot, log) = (dir.join("db.pdbr"), dir.join("db.wal"));
+    save_to_file(&common::sample_db(), &snapshot).unwrap();
+    let (mut db, mut wal) = open_with_wal(&snapshot, &log).unwrap();
+    let why = wal
+        .log(&mut db, append("name", "name", "other"))
+        .unwrap_err();
+    assert_eq!(why.kind(), ErrorKind::Conflict);
+    assert_eq!(db["name"]["name"], "test");
+    assert_eq!(std::fs::metadata(&log).unwrap().len(), 0);
+}